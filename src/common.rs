@@ -1,6 +1,59 @@
+use std::collections::HashMap;
+use std::hash::Hash;
 
 pub fn get_lines(filename :&str) -> Vec<String> {
     let contents = std::fs::read_to_string(filename)
         .expect("Could not read file");
     return contents.lines().map(str::to_string).collect();
 }
+
+// find where the sequence start, next(start), next(next(start)), ... starts repeating. Returns
+// (mu, lambda): the index of the first state that comes up again and the length of the cycle.
+// States are told apart by their fingerprint, which only needs to be as big as it takes to
+// identify a state, and each fingerprint is kept along with the index it was first seen at.
+pub fn find_cycle<T, K, F, G>(start: T, mut next: F, mut fingerprint: G) -> (usize, usize)
+where
+    K: Hash + Eq,
+    F: FnMut(&T) -> T,
+    G: FnMut(&T) -> K,
+{
+    let mut seen: HashMap<K, usize> = HashMap::new();
+    let mut state = start;
+    let mut i = 0;
+    loop {
+        let key = fingerprint(&state);
+        if let Some(&mu) = seen.get(&key) {
+            return (mu, i - mu);
+        }
+        seen.insert(key, i);
+        state = next(&state);
+        i += 1;
+    }
+}
+
+// the index of an earlier state that is the same as the n-th state, for a sequence with the
+// given (mu, lambda) from find_cycle.
+pub fn cycle_index(mu: usize, lambda: usize, n: usize) -> usize {
+    if n < mu {
+        n
+    } else {
+        mu + (n - mu) % lambda
+    }
+}
+
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_cycle() {
+        // 2, 4, 16, 256 % 100 = 56, 36, 96, 16, ...
+        let square = |x: &u64| x * x % 100;
+        assert_eq!(find_cycle(2, square, |x| *x), (2, 4));
+        assert_eq!(cycle_index(2, 4, 1), 1);
+        assert_eq!(cycle_index(2, 4, 1000000000), 4);
+
+        // the fingerprint only needs what changes between states: here the counter, not the step
+        // size that stays the same.
+        assert_eq!(find_cycle((0, 5), |&(a, step)| ((a + step) % 7, step), |s| s.0), (0, 7));
+    }
+}
@@ -1,271 +1,431 @@
 use crate::common::get_lines;
+use crate::polygon::interior_points;
+use std::collections::HashSet;
 
-fn parse_map(filename: &str) -> ((usize, usize), Vec<Vec<char>>) { 
-    let lines = get_lines(filename);
-    let mut map: Vec<Vec<char>> = Vec::new();
-    let mut start_pos = (0, 0);
-    for line in lines.iter() {
-        let mut row: Vec<char> = Vec::new();
-        for ch in line.chars() {
-            row.push(ch);
-            if ch == 'S' {
-                start_pos = (map.len(), row.len() - 1);
-            }
+type Position = (usize, usize); 
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dir {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Dir {
+    const ALL: [Dir; 4] = [Dir::North, Dir::East, Dir::South, Dir::West];
+
+    fn opposite(self) -> Dir {
+        match self {
+            Dir::North => Dir::South,
+            Dir::East => Dir::West,
+            Dir::South => Dir::North,
+            Dir::West => Dir::East,
+        }
+    }
+
+    // (row, col) step to take when moving in this direction.
+    fn offset(self) -> (isize, isize) {
+        match self {
+            Dir::North => (-1, 0),
+            Dir::East => (0, 1),
+            Dir::South => (1, 0),
+            Dir::West => (0, -1),
         }
-        map.push(row);
     }
-    return (start_pos, map);
 }
 
-fn get_starting_neighbors(map: &Vec<Vec<char>>, r: usize, c: usize) -> Vec<(usize, usize)> {
-    let mut neighbors: Vec<(usize, usize)> = Vec::new();
-    let ch = map[r][c];
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pipe {
+    Vertical,
+    Horizontal,
+    NorthEast,
+    NorthWest,
+    SouthWest,
+    SouthEast,
+    Ground,
+    // the starting tile, only until we've worked out which pipe is underneath.
+    Start,
+}
 
-    let valid_up = |c: char| -> bool { c == 'S' || c == '|' || c == '7' || c == 'F' };
-    let valid_down = |c: char| -> bool { c == 'S' || c == '|' || c == 'J' || c == 'L' };
-    let valid_left = |c: char| -> bool { c == 'S' || c == '-' || c == 'L' || c == 'F' };
-    let valid_right = |c: char| -> bool { c == 'S' || c == '-' || c == 'J' || c == '7' };
+// every kind of tile: how it's written in the input, how it's drawn, and which of north, east,
+// south and west it opens to.
+const PIPES: [(Pipe, char, char, [bool; 4]); 8] = [
+    (Pipe::Vertical,   '|', '│', [true,  false, true,  false]),
+    (Pipe::Horizontal, '-', '─', [false, true,  false, true ]),
+    (Pipe::NorthEast,  'L', '└', [true,  true,  false, false]),
+    (Pipe::NorthWest,  'J', '┘', [true,  false, false, true ]),
+    (Pipe::SouthWest,  '7', '┐', [false, false, true,  true ]),
+    (Pipe::SouthEast,  'F', '┌', [false, true,  true,  false]),
+    (Pipe::Ground,     '.', '.', [false, false, false, false]),
+    (Pipe::Start,      'S', 'S', [false, false, false, false]),
+];
 
-    match ch {
-        'S' => { 
-            if r > 0 && valid_up(map[r - 1][c]) {
-                neighbors.push((r - 1, c));
-            }
-            if r < map.len() - 1 && valid_down(map[r + 1][c]) {
-                neighbors.push((r + 1, c));
-            }
-            if c > 0 && valid_left(map[r][c - 1]) {
-                neighbors.push((r, c - 1));
-            }
-            if c < map[0].len() - 1 && valid_right(map[r][c + 1]) {
-                neighbors.push((r, c + 1));
-            }
-        },
-        _ => { panic!("not given a start token!") }
+impl Pipe {
+    fn entry(self) -> &'static (Pipe, char, char, [bool; 4]) {
+        PIPES.iter().find(|e| e.0 == self).unwrap()
     }
-    return neighbors;
-}
 
-type Position = (usize, usize); 
+    fn from_char(ch: char) -> Option<Pipe> {
+        PIPES.iter().find(|e| e.1 == ch).map(|e| e.0)
+    }
 
-fn get_next(prev: Position, curr: Position, map: &Vec<Vec<char>>) -> Option<Position> {
-    let (r, c) = curr;
-    let ch = map[r][c];
+    // the pipe that opens to exactly the given directions.
+    fn from_openings(openings: [bool; 4]) -> Option<Pipe> {
+        PIPES.iter().find(|e| e.0 != Pipe::Start && e.3 == openings).map(|e| e.0)
+    }
 
-    let valid_up = |c: char| -> bool { c == 'S' || c == '|' || c == '7' || c == 'F' };
-    let valid_down = |c: char| -> bool { c == 'S' || c == '|' || c == 'J' || c == 'L' };
-    let valid_left = |c: char| -> bool { c == 'S' || c == '-' || c == 'L' || c == 'F' };
-    let valid_right = |c: char| -> bool { c == 'S' || c == '-' || c == 'J' || c == '7' };
+    fn to_char(self) -> char {
+        self.entry().1
+    }
 
-    match ch {
-        '|' => { 
-            if r > 0 && valid_up(map[r - 1][c]) && (r - 1, c) != prev {
-                return Some((r - 1, c));
-            }
-            if r < map.len() - 1 && valid_down(map[r + 1][c]) && (r + 1, c) != prev {
-                return Some((r + 1, c));
-            }
-        },
-        '-' => { 
-            if c > 0 && valid_left(map[r][c - 1]) && (r, c - 1) != prev {
-                return Some((r, c - 1));
-            }
-            if c < map[0].len() - 1 && valid_right(map[r][c + 1]) && (r, c + 1) != prev  {
-                return Some((r, c + 1));
-            }
-        },
-        '7' => { 
-            if c > 0 && valid_left(map[r][c - 1]) && (r, c - 1) != prev { 
-                return Some((r, c - 1));
-            }
-            if r < map.len() - 1 && valid_down(map[r + 1][c]) && (r + 1, c) != prev {
-                return Some((r + 1, c));
-            }
-        },
-        'J' => { 
-            if c > 0 && valid_left(map[r][c - 1]) && (r, c - 1) != prev { 
-                return Some((r, c - 1));
-            }
-            if r > 0 && valid_up(map[r - 1][c]) && (r - 1, c) != prev {
-                return Some((r - 1, c));
-            }
-        },
-        'L' => { 
-            if c < map[0].len() - 1 && valid_right(map[r][c + 1]) && (r, c + 1) != prev {
-                return Some((r, c + 1));
-            }
-            if r > 0 && valid_up(map[r - 1][c]) && (r - 1, c) != prev {
-                return Some((r - 1, c));
-            }
-        }, 
-        'F' => { 
-            if c < map[0].len() - 1 && valid_right(map[r][c + 1]) && (r, c + 1) != prev {
-                return Some((r, c + 1));
-            }
-            if r < map.len() - 1 && valid_down(map[r + 1][c]) && (r + 1, c) != prev {
-                return Some((r + 1, c));
-            }
-        },
-        _ => {  }
+    // the pipe as a box drawing character.
+    fn glyph(self) -> char {
+        self.entry().2
     }
-    return None;
+
+    fn opens(self, dir: Dir) -> bool {
+        self.entry().3[dir as usize]
+    }
+
+    fn openings(self) -> impl Iterator<Item = Dir> {
+        Dir::ALL.into_iter().filter(move |&d| self.opens(d))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum StartPipeError {
+    // there's no S in the map.
+    MissingStart,
+    // fewer than two neighbors connect to S, so it can't be part of a loop.
+    NotEnoughConnections(usize),
+    // more than two neighbors connect to S, so we can't tell which of them the loop goes through.
+    Ambiguous(usize),
 }
 
-fn find_path(start_pos: Position, map: &Vec<Vec<char>>) -> Vec<Position> {
-    let mut path: Vec<Position> = Vec::new();
+struct PipeMap {
+    tiles: Vec<Vec<Pipe>>,
+    start: Position,
+}
+
+impl PipeMap {
+    // build the map and replace S with the pipe underneath, so nothing else needs to special case it.
+    fn new(lines: &[String]) -> Result<PipeMap, StartPipeError> {
+        let mut start = None;
+        let mut tiles: Vec<Vec<Pipe>> = Vec::new();
+        for (r, line) in lines.iter().enumerate() {
+            let mut row: Vec<Pipe> = Vec::new();
+            for (c, ch) in line.chars().enumerate() {
+                let pipe = Pipe::from_char(ch).unwrap_or_else(|| panic!("invalid tile {}", ch));
+                if pipe == Pipe::Start {
+                    start = Some((r, c));
+                }
+                row.push(pipe);
+            }
+            tiles.push(row);
+        }
+
+        let mut map = PipeMap { tiles, start: start.ok_or(StartPipeError::MissingStart)? };
+        let start_pipe = map.infer_start_pipe()?;
+        map.tiles[map.start.0][map.start.1] = start_pipe;
+        Ok(map)
+    }
+
+    fn parse(filename: &str) -> PipeMap {
+        PipeMap::new(&get_lines(filename)).expect("could not work out the pipe under S")
+    }
+
+    fn get(&self, (r, c): Position) -> Pipe {
+        self.tiles[r][c]
+    }
 
-    for path_start in get_starting_neighbors(&map, start_pos.0, start_pos.1) {
-        let mut prev = start_pos;
-        path.push(path_start);
-        while let Some(next) = get_next(prev, path[path.len() - 1], &map) {
+    fn neighbor(&self, (r, c): Position, dir: Dir) -> Option<Position> {
+        let (dr, dc) = dir.offset();
+        let r = r.checked_add_signed(dr)?;
+        let c = c.checked_add_signed(dc)?;
+        if r < self.tiles.len() && c < self.tiles[r].len() { Some((r, c)) } else { None }
+    }
+
+    // the neighbor in the given direction, if both pipes connect to each other.
+    fn connected(&self, pos: Position, dir: Dir) -> Option<Position> {
+        let next = self.neighbor(pos, dir)?;
+        if self.get(pos).opens(dir) && self.get(next).opens(dir.opposite()) { Some(next) } else { None }
+    }
+
+    // work out which pipe is hidden under S from the neighbors that connect to it.
+    fn infer_start_pipe(&self) -> Result<Pipe, StartPipeError> {
+        let mut openings = [false; 4];
+        for dir in Dir::ALL {
+            openings[dir as usize] = self.neighbor(self.start, dir)
+                .is_some_and(|next| self.get(next).opens(dir.opposite()));
+        }
+        match openings.iter().filter(|&&o| o).count() {
+            2 => Ok(Pipe::from_openings(openings).unwrap()),
+            n if n < 2 => Err(StartPipeError::NotEnoughConnections(n)),
+            n => Err(StartPipeError::Ambiguous(n)),
+        }
+    }
+
+    // walk the loop from the start, the last entry in the path is the start itself. Returns None
+    // if the pipes from the start don't loop back to it.
+    fn find_path(&self) -> Option<Vec<Position>> {
+        let mut path: Vec<Position> = Vec::new();
+
+        // the start connects both ways, so just head off in the first direction it opens to.
+        let mut pos = self.start;
+        let mut dir = self.get(pos).openings().next()?;
+        while let Some(next) = self.connected(pos, dir) {
             path.push(next);
-            prev = path[path.len() - 2];
-            if map[next.0][next.1] == 'S' {
-                return path;
+            if next == self.start {
+                return Some(path);
             }
+            // leave through the other opening of the pipe we came in through.
+            let came_from = dir.opposite();
+            let Some(out) = self.get(next).openings().find(|&d| d != came_from) else { break; };
+            pos = next;
+            dir = out;
         }
-        // no path found that loops to 'S'
-        path.clear();
+        // no path found that loops back to the start
+        None
     }
-    return path;
 }
 
 fn part1(filename: &str) -> usize {
-    let (start_pos, map) = parse_map(filename);
-    let path = find_path(start_pos, &map);
+    let map = PipeMap::parse(filename);
+    let path = map.find_path().unwrap_or_default();
     return path.len() / 2;
 }
 
 #[allow(dead_code)]
-fn print_path_in_map(path: &Vec<Position>, map: &Vec<Vec<char>>) {
-    let mut map_copy = map.clone();
-    for row in map_copy.iter_mut() {
-        for col in row.iter_mut() {
-            if *col != 'I' && *col != 'O' {
-                *col = '.';
+fn print_path_in_map(path: &[Position], map: &PipeMap) {
+    print!("{}", render_map(path, map, true));
+}
+
+const ANSI_RED: &str = "\x1b[1;31m";
+const ANSI_GREEN: &str = "\x1b[32m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+// draw the map with the main loop in box drawing characters, enclosed tiles as I and everything
+// else as '.'. With ansi the loop is drawn red and the enclosed tiles green.
+fn render_map(path: &[Position], map: &PipeMap, ansi: bool) -> String {
+    let on_loop = path.iter().cloned().collect::<HashSet<_>>();
+    let enclosed = enclosed_tiles(path, map);
+
+    let mut out = String::new();
+    for (r, row) in map.tiles.iter().enumerate() {
+        for (c, &pipe) in row.iter().enumerate() {
+            if on_loop.contains(&(r, c)) {
+                if ansi { out += ANSI_RED; }
+                out.push(pipe.glyph());
+                if ansi { out += ANSI_RESET; }
+            } else if enclosed.contains(&(r, c)) {
+                if ansi { out += ANSI_GREEN; }
+                out.push('I');
+                if ansi { out += ANSI_RESET; }
+            } else {
+                out.push('.');
             }
         }
+        out.push('\n');
     }
-    for pos in path.iter() {
-        map_copy[pos.0][pos.1] = map[pos.0][pos.1];
-    }
-    for row in map_copy.iter() {
-        println!("{}", row.iter().collect::<String>());
-    }
+    out
 }
 
-fn part2(filename: &str) -> usize {
-    let (start_pos, map) = parse_map(filename);
-    let mut map_copy = map.clone();
-    let path = find_path(start_pos, &map);
-    let path_set = path.iter().cloned().collect::<std::collections::HashSet<_>>();
-
-    // clear the map_copy and mark the path
-    for row in map_copy.iter_mut() {
-        for col in row.iter_mut() {
-            *col = '.';
+// draw the map as a binary PPM image with 3x3 pixels per tile: the main loop in red, other pipes
+// in grey and enclosed tiles filled in green.
+fn render_ppm(path: &[Position], map: &PipeMap) -> Vec<u8> {
+    const SCALE: usize = 3;
+    const BACKGROUND: [u8; 3] = [0, 0, 0];
+    const LOOP: [u8; 3] = [255, 0, 0];
+    const PIPE: [u8; 3] = [96, 96, 96];
+    const ENCLOSED: [u8; 3] = [0, 160, 0];
+
+    let on_loop = path.iter().cloned().collect::<HashSet<_>>();
+    let enclosed = enclosed_tiles(path, map);
+    let height = map.tiles.len() * SCALE;
+    let width = map.tiles.iter().map(|row| row.len()).max().unwrap_or(0) * SCALE;
+    let mut pixels = vec![BACKGROUND; width * height];
+
+    for (r, row) in map.tiles.iter().enumerate() {
+        for (c, &pipe) in row.iter().enumerate() {
+            let mut set = |dr: usize, dc: usize, color: [u8; 3]| {
+                pixels[(r * SCALE + dr) * width + c * SCALE + dc] = color;
+            };
+            if enclosed.contains(&(r, c)) {
+                for i in 0..SCALE * SCALE {
+                    set(i / SCALE, i % SCALE, ENCLOSED);
+                }
+                continue;
+            }
+            if pipe == Pipe::Ground {
+                continue;
+            }
+            let color = if on_loop.contains(&(r, c)) { LOOP } else { PIPE };
+            // the centre of the tile plus an arm towards each side the pipe opens to.
+            set(1, 1, color);
+            for dir in pipe.openings() {
+                let (dr, dc) = dir.offset();
+                set((1 + dr) as usize, (1 + dc) as usize, color);
+            }
         }
     }
-    for pos in path.iter() {
-        map_copy[pos.0][pos.1] = map[pos.0][pos.1];
-    }
 
+    let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    ppm.extend(pixels.iter().flatten());
+    ppm
+}
 
-    let mut inside_positions = Vec::new();
-    let mut outside_positions = Vec::new();
-
-    let mut mark_map = |pos: &Position, outside_offset: (isize, isize), inside_offset: (isize, isize)| {
-        let out_pos = (pos.0 as isize + outside_offset.0, pos.1 as isize + outside_offset.1);
-        let in_pos = (pos.0 as isize + inside_offset.0, pos.1 as isize + inside_offset.1);
-        if out_pos.0 >= 0 && out_pos.0 < map_copy.len() as isize && 
-           out_pos.1 >= 0 && out_pos.1 < map_copy[0].len() as isize && 
-           !path_set.contains(&(out_pos.0 as usize, out_pos.1 as usize)) {
-            outside_positions.push((out_pos.0 as usize, out_pos.1 as usize));
-            map_copy[out_pos.0 as usize][out_pos.1 as usize] = 'O';
-        } 
-        if in_pos.0 >= 0 && in_pos.0 < map_copy.len() as isize && 
-           in_pos.1 >= 0 && in_pos.1 < map_copy[0].len() as isize && 
-           !path_set.contains(&(in_pos.0 as usize, in_pos.1 as usize)) {
-            inside_positions.push((in_pos.0 as usize, in_pos.1 as usize));
-            map_copy[in_pos.0 as usize][in_pos.1 as usize] = 'I';
-        }
-    };
-
-    // find the starting inside/outside offset
-    let diff = (path[0].0 as isize - start_pos.0 as isize, path[0].1 as isize - start_pos.1 as isize);
-    let mut inside_offset = (diff.1, diff.0);
-    let mut outside_offset = (-diff.1, -diff.0);
-
-    // track the inside/outside offsets and mark along the path
-    let mut path_iter = path.iter();
-    while let Some(pos) = path_iter.next() { 
-        let ch = map[pos.0][pos.1];
-        if ch == 'S' {
-            break;
+fn write_ppm(filename: &str, ppm_filename: &str) {
+    let map = PipeMap::parse(filename);
+    let path = map.find_path().unwrap_or_default();
+    std::fs::write(ppm_filename, render_ppm(&path, &map)).expect("Could not write file");
+}
+
+// the pipe map as a Graphviz DOT graph with one node per pipe tile, laid out on the grid. Tiles on
+// the main loop are red, everything else is grey. Render it with e.g.
+// `neato -n -Tsvg pipes.dot -o pipes.svg`.
+fn pipes_to_dot(path: &[Position], map: &PipeMap) -> String {
+    let on_loop = path.iter().cloned().collect::<HashSet<_>>();
+
+    let mut dot = String::from("graph pipes {\n    node [shape=box, fontname=monospace];\n");
+    for (r, row) in map.tiles.iter().enumerate() {
+        for (c, &pipe) in row.iter().enumerate() {
+            if pipe == Pipe::Ground {
+                continue;
+            }
+            let color = if on_loop.contains(&(r, c)) { "red" } else { "grey" };
+            // 72 points per grid cell, with y pointing up in graphviz.
+            dot += &format!("    \"{}_{}\" [label=\"{}\", color={}, pos=\"{},{}\"];\n",
+                            r, c, pipe.to_char(), color, c * 72, (map.tiles.len() - r) * 72);
         }
-        //println!("pos: {:?}, outside: {:?}, inside: {:?}", pos, outside_offset, inside_offset);
-        mark_map(pos, outside_offset, inside_offset);
-        if ch == 'F' || ch == 'J' {
-            // swap to maintain winding order
-            outside_offset = (outside_offset.1, outside_offset.0);
-            inside_offset = (inside_offset.1, inside_offset.0);
-            // mark again once we've rotated on the path
-            mark_map(pos, outside_offset, inside_offset);
-        } else if ch == 'L' || ch == '7' {
-            // swap and flip to maintain winding order
-            outside_offset = (-outside_offset.1, -outside_offset.0);
-            inside_offset = (-inside_offset.1, -inside_offset.0);
-            // mark again once we've rotated on the path
-            mark_map(pos, outside_offset, inside_offset);
+    }
+    for (r, row) in map.tiles.iter().enumerate() {
+        for c in 0..row.len() {
+            // only look right and down so every edge is written once.
+            for dir in [Dir::East, Dir::South] {
+                let Some(to) = map.connected((r, c), dir) else { continue; };
+                let color = if on_loop.contains(&(r, c)) && on_loop.contains(&to) { "red" } else { "grey" };
+                dot += &format!("    \"{}_{}\" -- \"{}_{}\" [color={}];\n", r, c, to.0, to.1, color);
+            }
         }
     }
+    dot += "}\n";
+    dot
+}
 
-    let mut flood_fill = |pos: Position, ch: char| {
-        let mut stack = vec![pos];
-        while let Some(pos) = stack.pop() {
-            map_copy[pos.0][pos.1] = ch;
-            if pos.0 > 0 && map_copy[pos.0 - 1][pos.1] == '.' { stack.push((pos.0 - 1, pos.1)); }
-            if pos.0 < map_copy.len() - 1 && map_copy[pos.0+1][pos.1] == '.' { stack.push((pos.0 + 1, pos.1)); }
-            if pos.1 > 0 && map_copy[pos.0][pos.1-1] == '.' { stack.push((pos.0, pos.1 - 1)); }
-            if pos.1 < map_copy[0].len() - 1 && map_copy[pos.0][pos.1 + 1] == '.' { stack.push((pos.0, pos.1 + 1)); }
-        }
-    };
+fn write_pipes_dot(filename: &str, dot_filename: &str) {
+    let map = PipeMap::parse(filename);
+    let path = map.find_path().unwrap_or_default();
+    std::fs::write(dot_filename, pipes_to_dot(&path, &map)).expect("Could not write file");
+}
 
-    // flood fill
-    for pos in inside_positions.iter() {
-        flood_fill(*pos, 'I');
-    }
-    for pos in outside_positions.iter() {
-        flood_fill(*pos, 'O');
-    }
+// count the tiles enclosed by the loop: the loop tiles are the vertices of a polygon, and since the
+// loop visits every boundary tile Pick's theorem gives the number of tiles strictly inside.
+fn part2(filename: &str) -> usize {
+    let map = PipeMap::parse(filename);
+    // without a loop nothing is enclosed, and Pick's theorem doesn't apply.
+    let Some(path) = map.find_path() else { return 0; };
+    let pts = path.iter().map(|&(r, c)| (r as isize, c as isize)).collect::<Vec<_>>();
+    interior_points(&pts, path.len() as isize) as usize
+}
+
+// find the enclosed tiles by scanning each row and flipping between inside and outside every time
+// we cross a loop tile that connects upwards.
+fn enclosed_tiles(path: &[Position], map: &PipeMap) -> HashSet<Position> {
+    let path_set = path.iter().cloned().collect::<HashSet<_>>();
 
-    // count the O's and I's n map_copy
-    let mut outside_count = 0;
-    let mut inside_count = 0;
-    let mut dot_count = 0;
-    for row in map_copy.iter() {
-        for col in row.iter() {
-            if *col == 'O' {
-                outside_count += 1;
-            } else if *col == 'I' {
-                inside_count += 1;
-            } else if *col == '.' {
-                dot_count += 1;
+    let mut enclosed = HashSet::new();
+    for (r, row) in map.tiles.iter().enumerate() {
+        let mut inside = false;
+        for (c, &pipe) in row.iter().enumerate() {
+            if path_set.contains(&(r, c)) {
+                if pipe.opens(Dir::North) {
+                    inside = !inside;
+                }
+            } else if inside {
+                enclosed.insert((r, c));
             }
         }
     }
+    enclosed
+}
 
-    // print_path_in_map(&path, &map_copy);
-    println!("outside: {}, inside: {}, unvisited: {}", outside_count, inside_count, dot_count);
-    if dot_count > 0 {
-        panic!("Found unvisited positions!");
-    }
-    std::cmp::min(outside_count, inside_count)
+fn part2_scanline(filename: &str) -> usize {
+    let map = PipeMap::parse(filename);
+    let path = map.find_path().unwrap_or_default();
+    enclosed_tiles(&path, &map).len()
 }
 
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_dot() {
+        let map = PipeMap::parse("files/day10_sample.txt");
+        let path = map.find_path().unwrap();
+        let dot = pipes_to_dot(&path, &map);
+        assert!(dot.starts_with("graph pipes {"));
+        assert_eq!(dot.matches("color=red, pos=").count(), path.len());
+        assert_eq!(dot.matches("[color=red];").count(), path.len());
+        assert!(dot.contains("\"1_1\" [label=\"F\", color=red, pos=\"72,288\"];"));
+        assert!(dot.contains("\"1_1\" -- \"1_2\" [color=red];"));
+
+        let out = std::env::temp_dir().join("day10_sample.dot");
+        write_pipes_dot("files/day10_sample.txt", out.to_str().unwrap());
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), dot);
+    }
+
+    #[test]
+    fn test_render() {
+        let map = PipeMap::parse("files/day10_sample3.txt");
+        let path = map.find_path().unwrap();
+        let rendered = render_map(&path, &map, false);
+        let lines = rendered.lines().collect::<Vec<&str>>();
+        assert_eq!(lines[1], ".┌───────┐.");
+        assert_eq!(lines[6], ".│II│.│II│.");
+        assert_eq!(rendered.matches('I').count(), 4);
+        assert!(render_map(&path, &map, true).contains(&format!("{}I{}", ANSI_GREEN, ANSI_RESET)));
+
+        let ppm = render_ppm(&path, &map);
+        let (rows, cols) = (map.tiles.len(), map.tiles[0].len());
+        let header = format!("P6\n{} {}\n255\n", cols * 3, rows * 3);
+        assert!(ppm.starts_with(header.as_bytes()));
+        assert_eq!(ppm.len(), header.len() + rows * cols * 27);
+
+        let out = std::env::temp_dir().join("day10_sample3.ppm");
+        write_ppm("files/day10_sample3.txt", out.to_str().unwrap());
+        assert_eq!(std::fs::read(&out).unwrap(), ppm);
+    }
+
+    #[test]
+    fn test_start_pipe() {
+        assert_eq!(PipeMap::parse("files/day10_sample2.txt").get((2, 0)), Pipe::SouthEast);
+        assert_eq!(PipeMap::parse("files/day10_sample4.txt").get((4, 12)), Pipe::SouthEast);
+        assert_eq!(PipeMap::parse("files/day10_input.txt").get((57, 65)), Pipe::Vertical);
+
+        let lines = |rows: &[&str]| rows.iter().map(|r| r.to_string()).collect::<Vec<String>>();
+        assert_eq!(PipeMap::new(&lines(&["-S-"])).unwrap().get((0, 1)), Pipe::Horizontal);
+        assert_eq!(PipeMap::new(&lines(&[".|.", "-S-", ".|."])).err(), Some(StartPipeError::Ambiguous(4)));
+        assert_eq!(PipeMap::new(&lines(&[".S-"])).err(), Some(StartPipeError::NotEnoughConnections(1)));
+        assert_eq!(PipeMap::new(&lines(&["-|-"])).err(), Some(StartPipeError::MissingStart));
+
+        // the start pipe fits, but the pipes next to it lead nowhere.
+        assert_eq!(PipeMap::new(&lines(&["-S-"])).unwrap().find_path(), None);
+        assert_eq!(part1("files/day10_noloop.txt"), 0);
+        assert_eq!(part2("files/day10_noloop.txt"), 0);
+        assert_eq!(part2_scanline("files/day10_noloop.txt"), 0);
+    }
+
+    #[test]
+    fn test_pipe_table() {
+        for (pipe, ch, _, openings) in PIPES.iter() {
+            assert_eq!(Pipe::from_char(*ch), Some(*pipe));
+            assert_eq!(pipe.openings().count(), openings.iter().filter(|&&o| o).count());
+        }
+        assert_eq!(Pipe::from_openings([true, true, false, false]), Some(Pipe::NorthEast));
+        assert_eq!(Pipe::NorthWest.openings().collect::<Vec<Dir>>(), vec![Dir::North, Dir::West]);
+    }
+
     #[test]
     fn test() {
         assert_eq!(part1("files/day10_sample.txt"), 4);
@@ -275,5 +435,9 @@ mod tests {
         assert_eq!(part2("files/day10_sample4.txt"), 8);
         assert_eq!(part2("files/day10_sample5.txt"), 10);
         assert_eq!(part2("files/day10_input.txt"), 563);
+        assert_eq!(part2_scanline("files/day10_sample3.txt"), 4);
+        assert_eq!(part2_scanline("files/day10_sample4.txt"), 8);
+        assert_eq!(part2_scanline("files/day10_sample5.txt"), 10);
+        assert_eq!(part2_scanline("files/day10_input.txt"), 563);
     }
 }
\ No newline at end of file
@@ -1,64 +1,222 @@
 use crate::common::get_lines;
+use std::collections::{BTreeMap, HashMap};
 
-fn parse_map(filename: &str, expansion: usize) -> (Vec<Vec<(char, usize, usize)>>, Vec<(usize, usize)>) {
+type Position = (usize, usize);
+
+// galaxy positions as (row, col).
+fn parse_galaxies(filename: &str) -> Vec<Position> {
     let lines = get_lines(filename);
-    // keep a list of (char, x, y) tuples where x is cost of 
-    // traversing current row, y is cost of traversing current column.
-    let mut map: Vec<Vec<(char, usize, usize)>> = Vec::new();
     let mut galaxy_positions = Vec::new();
-    for line in lines {
-        let row_cost: usize = if line.chars().all(|c| c == '.') { expansion } else { 1 };
-        let mut row = Vec::new();
-        for c in line.chars() {
-            if c == '#' {
-                galaxy_positions.push((map.len(), row.len()));
+    for (r, line) in lines.iter().enumerate() {
+        for (c, ch) in line.chars().enumerate() {
+            if ch == '#' {
+                galaxy_positions.push((r, c));
             }
-            row.push((c, row_cost, 1));
         }
-        map.push(row);
     }
+    return galaxy_positions;
+}
+
+// how much space the empty rows and columns (the ones without any galaxy) take up.
+struct Expansion {
+    rows: usize,
+    cols: usize,
+    // sizes of particular empty rows and columns, overriding the factors above.
+    row_weights: BTreeMap<usize, usize>,
+    col_weights: BTreeMap<usize, usize>,
+}
+
+impl Expansion {
+    fn new(rows: usize, cols: usize) -> Expansion {
+        Expansion { rows, cols, row_weights: BTreeMap::new(), col_weights: BTreeMap::new() }
+    }
+
+    fn uniform(factor: usize) -> Expansion {
+        Expansion::new(factor, factor)
+    }
+
+    fn with_row_weight(mut self, row: usize, weight: usize) -> Expansion {
+        self.row_weights.insert(row, weight);
+        self
+    }
+
+    fn with_col_weight(mut self, col: usize, weight: usize) -> Expansion {
+        self.col_weights.insert(col, weight);
+        self
+    }
+}
+
+// move each coordinate so that every empty line before it takes up `factor` lines instead of one,
+// or its weight if it has one. Coordinates are returned in the same order they were given.
+fn expand(coords: &[usize], factor: usize, weights: &BTreeMap<usize, usize>) -> Vec<u128> {
+    let mut sorted = coords.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
 
-    // find empty columns and update their costs.
-    for c in 0..map[0].len() { 
-        let col_cost = if map.iter().enumerate().all(|(r, _)| map[r][c].0 == '.') { expansion } else { 1 };
-        for r in 0..map.len() {
-            map[r][c].2 = col_cost;
+    // expanded coordinate of each distinct line holding a galaxy.
+    let mut expanded: HashMap<usize, u128> = HashMap::with_capacity(sorted.len());
+    // a weight below one shrinks the line, so the offset can go negative.
+    let mut offset: i128 = 0;
+    for (i, &x) in sorted.iter().enumerate() {
+        if i > 0 {
+            let prev = sorted[i - 1];
+            let empty_lines = (x - prev - 1) as i128;
+            offset += empty_lines * (factor as i128 - 1);
+            // lines with a weight count as their weight instead of the factor.
+            for (_, &w) in weights.range(prev + 1..x) {
+                offset += w as i128 - factor as i128;
+            }
         }
+        expanded.insert(x, (x as i128 + offset) as u128);
     }
-    return (map, galaxy_positions);
+    coords.iter().map(|x| expanded[x]).collect()
 }
 
-fn solve(filename: &str, expansion: usize) -> usize {
-    let (map, galaxy_positions) = parse_map(filename, expansion);
-    //println!("{:?}", map);
+// sum of |a - b| over all pairs of sorted values: each value is larger than all the values before
+// it, so it contributes i * value minus the sum of those values.
+fn pairwise_distance_sum(sorted: &[u128]) -> u128 {
     let mut sum = 0;
-    for i in 0..galaxy_positions.len() {
-        for j in i+1..galaxy_positions.len() {
-            let (r1, c1) = galaxy_positions[i];
-            let (r2, c2) = galaxy_positions[j];
-            let from_r = if r1 < r2 { r1 } else { r2 };
-            let to_r = if r1 < r2 { r2 } else { r1 };
-            let from_c = if c1 < c2 { c1 } else { c2 };
-            let to_c = if c1 < c2 { c2 } else { c1 };
-
-            // compute cost of manhattan path.
-            let mut cost = 0;
-            for r in from_r..to_r {
-                cost += map[r][from_c].1;
-            }
-            for c in from_c..to_c {
-                cost += map[from_r][c].2;
+    let mut prefix = 0;
+    for (i, &x) in sorted.iter().enumerate() {
+        sum += i as u128 * x - prefix;
+        prefix += x;
+    }
+    sum
+}
+
+fn manhattan(a: (u128, u128), b: (u128, u128)) -> u128 {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+// the galaxies after expansion. Galaxies are named 1, 2, 3, ... in the order they appear in the
+// input, reading left to right and top to bottom.
+struct Universe {
+    galaxies: Vec<(u128, u128)>,
+}
+
+impl Universe {
+    fn new(galaxies: &[Position], expansion: &Expansion) -> Universe {
+        let rows = galaxies.iter().map(|g| g.0).collect::<Vec<usize>>();
+        let cols = galaxies.iter().map(|g| g.1).collect::<Vec<usize>>();
+        let rows = expand(&rows, expansion.rows, &expansion.row_weights);
+        let cols = expand(&cols, expansion.cols, &expansion.col_weights);
+        Universe { galaxies: rows.into_iter().zip(cols).collect() }
+    }
+
+    fn parse(filename: &str, expansion: &Expansion) -> Universe {
+        Universe::new(&parse_galaxies(filename), expansion)
+    }
+
+    fn position(&self, galaxy: usize) -> (u128, u128) {
+        self.galaxies[galaxy - 1]
+    }
+
+    fn distance(&self, a: usize, b: usize) -> u128 {
+        manhattan(self.position(a), self.position(b))
+    }
+
+    // the manhattan distance splits into rows and columns, so the sum over all pairs can be
+    // computed for each axis separately in O(n log n) without looking at the map at all.
+    fn total_distance(&self) -> u128 {
+        let mut rows = self.galaxies.iter().map(|g| g.0).collect::<Vec<u128>>();
+        let mut cols = self.galaxies.iter().map(|g| g.1).collect::<Vec<u128>>();
+        rows.sort_unstable();
+        cols.sort_unstable();
+        pairwise_distance_sum(&rows) + pairwise_distance_sum(&cols)
+    }
+
+    // the two closest galaxies and their distance. Sweep down the rows, only comparing against
+    // earlier galaxies that are fewer rows above than the best distance so far.
+    fn nearest_pair(&self) -> Option<(usize, usize, u128)> {
+        let mut order = (0..self.galaxies.len()).collect::<Vec<usize>>();
+        order.sort_unstable_by_key(|&i| self.galaxies[i]);
+
+        let mut best: Option<(usize, usize, u128)> = None;
+        for (k, &i) in order.iter().enumerate() {
+            for &j in order[..k].iter().rev() {
+                if best.is_some_and(|b| self.galaxies[i].0 - self.galaxies[j].0 >= b.2) {
+                    break;
+                }
+                let d = manhattan(self.galaxies[i], self.galaxies[j]);
+                if best.is_none_or(|b| d < b.2) {
+                    best = Some((i.min(j) + 1, i.max(j) + 1, d));
+                }
             }
-            //println!("from {} to {} cost {}", i+1, j+1, cost);
-            sum += cost;
         }
+        best
+    }
+
+    // the two galaxies furthest apart and their distance. The manhattan distance is the larger
+    // difference of either row + col or row - col, so only the extremes of those matter.
+    fn farthest_pair(&self) -> Option<(usize, usize, u128)> {
+        let sum = |i: &usize| self.galaxies[*i].0 as i128 + self.galaxies[*i].1 as i128;
+        let diff = |i: &usize| self.galaxies[*i].0 as i128 - self.galaxies[*i].1 as i128;
+        let ids = 0..self.galaxies.len();
+        let candidates = [
+            (ids.clone().min_by_key(sum)?, ids.clone().max_by_key(sum)?),
+            (ids.clone().min_by_key(diff)?, ids.clone().max_by_key(diff)?),
+        ];
+        candidates.iter()
+            .map(|&(i, j)| (i.min(j) + 1, i.max(j) + 1, manhattan(self.galaxies[i], self.galaxies[j])))
+            .max_by_key(|p| p.2)
     }
-    return sum;
+}
+
+fn solve(filename: &str, expansion: usize) -> u128 {
+    Universe::parse(filename, &Expansion::uniform(expansion)).total_distance()
 }
 
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_many_galaxies() {
+        // a diagonal of galaxies with an empty row and column between each pair.
+        let galaxies = (0..20000).map(|i| (2 * i, 2 * i)).collect::<Vec<Position>>();
+        // the distance between galaxies i and j is 2 * |i - j| * (1 + expansion).
+        let n = galaxies.len() as u128;
+        let expected = |expansion: u128| 2 * (1 + expansion) * (n - 1) * n * (n + 1) / 6;
+        assert_eq!(Universe::new(&galaxies, &Expansion::uniform(2)).total_distance(), expected(2));
+        assert_eq!(Universe::new(&galaxies, &Expansion::uniform(1000000000)).total_distance(), expected(1000000000));
+        let universe = Universe::new(&galaxies, &Expansion::uniform(10));
+        assert_eq!(universe.nearest_pair(), Some((1, 2, 22)));
+        assert_eq!(universe.farthest_pair(), Some((1, 20000, 22 * 19999)));
+    }
+
+    #[test]
+    fn test_universe() {
+        let universe = Universe::parse("files/day11_sample.txt", &Expansion::uniform(2));
+        assert_eq!(universe.distance(5, 9), 9);
+        assert_eq!(universe.distance(1, 7), 15);
+        assert_eq!(universe.distance(3, 6), 17);
+        assert_eq!(universe.distance(8, 9), 5);
+        // 2 and 4 are also 5 apart.
+        let (a, b, d) = universe.nearest_pair().unwrap();
+        assert_eq!((universe.distance(a, b), d), (5, 5));
+        assert_eq!(universe.farthest_pair(), Some((2, 8, 19)));
+
+        // brute force check of the pair queries.
+        let n = universe.galaxies.len();
+        let distances = (1..=n).flat_map(|a| (a + 1..=n).map(move |b| (a, b)))
+            .map(|(a, b)| universe.distance(a, b))
+            .collect::<Vec<u128>>();
+        assert_eq!(distances.iter().min(), Some(&5));
+        assert_eq!(distances.iter().max(), Some(&19));
+        assert_eq!(distances.iter().sum::<u128>(), 374);
+
+        // only stretch the columns, the empty rows 3 and 7 stay the same size.
+        let universe = Universe::parse("files/day11_sample.txt", &Expansion::new(1, 2));
+        assert_eq!(universe.distance(1, 7), 13);
+
+        // empty column 5 is a million wide and row 3 is empty space no more, the other empty lines
+        // are twice as wide.
+        let expansion = Expansion::uniform(2).with_col_weight(5, 1000000).with_row_weight(3, 0);
+        let universe = Universe::parse("files/day11_sample.txt", &expansion);
+        assert_eq!(universe.distance(1, 2), 4 + 1000000);
+        assert_eq!(universe.distance(1, 3), 6);
+        assert_eq!(universe.distance(3, 4), 1 + 6 + 1000000);
+    }
+
     #[test]
     fn test() {
         assert_eq!(solve("files/day11_sample.txt", 2), 374);
@@ -67,4 +225,4 @@ mod tests {
         assert_eq!(solve("files/day11_sample.txt", 100), 8410);
         assert_eq!(solve("files/day11_input.txt", 1000000), 374);
     }
-}
\ No newline at end of file
+}
@@ -1,6 +1,7 @@
 use crate::common::get_lines;
 use std::collections::VecDeque;
 use std::collections::HashMap;
+use num::{BigUint, Zero};
 
 // find next valid arrangement for n springs starting at offset
 // if found, return the index of the next startin offset.
@@ -16,6 +17,8 @@ fn find_arrangement(chars: &Vec<char>, offset: usize, n: usize) -> Option<usize>
 }
 
 // find all arragements for all nums starting at num_offset within chars starting at offset.
+// Kept to compare against ArrangementTable.
+#[allow(dead_code)]
 fn find_all_arrangements(chars: &Vec<char>, offset: usize, nums: &VecDeque<usize>, num_offset: usize, 
                          cache: &mut HashMap<(usize, usize), usize>) -> usize {
     let mut count = 0;
@@ -63,38 +66,408 @@ fn find_all_arrangements(chars: &Vec<char>, offset: usize, nums: &VecDeque<usize
     return count
 }
 
-fn find_arrangements_in_line(line: &str, repeat: usize) -> usize {
+// the springs and group sizes of a single copy of a line.
+fn parse_line(line: &str) -> (Vec<char>, VecDeque<usize>) {
     let mut tokens = line.split_whitespace();
-    let group = tokens.next().unwrap();
-    let mut chars: Vec<char> = group.chars().collect();
+    let chars: Vec<char> = tokens.next().unwrap().chars().collect();
 
     let num_str = tokens.next().unwrap().split(',');
     let mut nums = VecDeque::<usize>::new();
     for num in num_str {
         nums.push_back(num.parse::<usize>().unwrap());
     }
+    (chars, nums)
+}
+
+// `copies` copies of the springs with `joiner` between each pair, and the group sizes repeated as
+// many times.
+fn unfold(chars: &[char], nums: &VecDeque<usize>, copies: usize, joiner: char) -> (Vec<char>, VecDeque<usize>) {
+    let mut unfolded_chars = Vec::with_capacity(copies * (chars.len() + 1));
+    let mut unfolded_nums = VecDeque::with_capacity(copies * nums.len());
+    for i in 0..copies {
+        if i > 0 {
+            unfolded_chars.push(joiner);
+        }
+        unfolded_chars.extend_from_slice(chars);
+        unfolded_nums.extend(nums.iter().copied());
+    }
+    (unfolded_chars, unfolded_nums)
+}
+
+fn unfold_line(line: &str, copies: usize, joiner: char) -> (Vec<char>, VecDeque<usize>) {
+    let (chars, nums) = parse_line(line);
+    unfold(&chars, &nums, copies, joiner)
+}
+
+// number of ways of placing groups[k..] within chars[offset..], for every offset and k, filled in
+// bottom-up from the end of the line and the last group.
+struct ArrangementTable {
+    n_groups: usize,
+    // (chars.len() + 2) x (n_groups + 1), an offset of chars.len() + 1 means we've gone past the end
+    // after placing a group that ends on the last char. Counts grow exponentially with the number
+    // of copies of a line, so they don't fit in any fixed size integer.
+    counts: Vec<BigUint>,
+}
+
+impl ArrangementTable {
+    fn new(chars: &[char], nums: &VecDeque<usize>) -> ArrangementTable {
+        let len = chars.len();
+        let n_groups = nums.len();
 
-    // extend the group and nums repeat times
-    let nums_copy = nums.clone();
-    for _ in 0..repeat {
-        chars.push('?');
-        for c in group.chars() {
-            chars.push(c);
+        // length of the run of non-'.' chars starting at each position.
+        let mut run = vec![0; len + 1];
+        for i in (0..len).rev() {
+            run[i] = if chars[i] == '.' { 0 } else { run[i + 1] + 1 };
         }
-        nums.extend(nums_copy.clone());
+
+        let mut table = ArrangementTable { n_groups, counts: vec![BigUint::zero(); (len + 2) * (n_groups + 1)] };
+        // with all groups placed, the rest of the line must be free of springs.
+        table.set(len + 1, n_groups, 1u32.into());
+        table.set(len, n_groups, 1u32.into());
+        for i in (0..len).rev() {
+            let rest = if chars[i] == '#' { BigUint::zero() } else { table.get(i + 1, n_groups).clone() };
+            table.set(i, n_groups, rest);
+        }
+
+        for k in (0..n_groups).rev() {
+            let n = nums[k];
+            for i in (0..len).rev() {
+                let mut count = BigUint::zero();
+                // leave this position empty.
+                if chars[i] != '#' {
+                    count += table.get(i + 1, k);
+                }
+                // start the group here, it can't be directly followed by another spring.
+                if run[i] >= n && (i + n == len || chars[i + n] != '#') {
+                    count += table.get(i + n + 1, k + 1);
+                }
+                table.set(i, k, count);
+            }
+        }
+        table
+    }
+
+    fn get(&self, offset: usize, k: usize) -> &BigUint {
+        &self.counts[offset * (self.n_groups + 1) + k]
     }
 
+    fn set(&mut self, offset: usize, k: usize, count: BigUint) {
+        self.counts[offset * (self.n_groups + 1) + k] = count;
+    }
+}
+
+// number of ways to place groups of springs of the given sizes, in order, within chars.
+pub fn count_arrangements(chars: &[char], nums: &VecDeque<usize>) -> BigUint {
+    ArrangementTable::new(chars, nums).get(0, 0).clone()
+}
+
+fn find_arrangements_in_line(line: &str, copies: usize, joiner: char) -> BigUint {
+    let (chars, nums) = unfold_line(line, copies, joiner);
+
     // println!("chars: {:?}, nums: {:?}", chars, nums);
-    let mut cache: HashMap<(usize, usize), usize> = HashMap::new();
-    find_all_arrangements(&chars, 0, &nums, 0, &mut cache)
+    count_arrangements(&chars, &nums)
+}
+
+// state of the springs automaton: the number of groups finished, counted relative to the copies
+// of the line read so far, and how many springs of the current group have been placed.
+type Boundary = (isize, usize);
+
+// feed one copy's chars to the automaton starting from `from`, returning the number of ways of
+// ending up in each state. Group d has size nums[d mod nums.len()] since the groups repeat.
+fn feed(chars: &[char], nums: &VecDeque<usize>, from: Boundary, modulus: u64) -> HashMap<Boundary, u128> {
+    let m = nums.len() as isize;
+    let mut states = HashMap::from([(from, 1)]);
+    for &ch in chars {
+        let mut next: HashMap<Boundary, u128> = HashMap::new();
+        for (&(d, r), &count) in states.iter() {
+            let size = nums[d.rem_euclid(m) as usize];
+            let mut add = |state: Boundary| {
+                let c = next.entry(state).or_insert(0);
+                *c = (*c + count) % modulus as u128;
+            };
+            if r == 0 {
+                if ch != '#' {
+                    add((d, 0));
+                }
+                if ch != '.' {
+                    add((d, 1));
+                }
+            } else if r < size {
+                if ch != '.' {
+                    add((d, r + 1));
+                }
+            } else if ch != '#' {
+                // the group is done, it has to be followed by an empty spot.
+                add((d + 1, 0));
+            }
+        }
+        states = next;
+    }
+    // one more copy of the groups has been read.
+    states.into_iter().map(|((d, r), count)| ((d - m, r), count)).collect()
+}
+
+fn mat_mul(a: &[Vec<u128>], b: &[Vec<u128>], modulus: u64) -> Vec<Vec<u128>> {
+    let n = b[0].len();
+    a.iter().map(|row| {
+        let mut out = vec![0; n];
+        for (k, &x) in row.iter().enumerate() {
+            if x == 0 {
+                continue;
+            }
+            for (o, &y) in out.iter_mut().zip(b[k].iter()) {
+                *o = (*o + x * y) % modulus as u128;
+            }
+        }
+        out
+    }).collect()
+}
+
+// most boundary states count_unfolded_mod builds a transfer matrix for.
+const MAX_STATES: usize = 512;
+
+// most groups that can be finished within the chars: greedily packing groups of the smallest size,
+// plus one more that was started before them.
+fn max_groups(chars: &[char], smallest: usize) -> usize {
+    let mut groups = 1;
+    let mut run = 0;
+    for &ch in chars {
+        if ch == '.' || run > smallest {
+            // a gap, or the spot right after a group which has to be one.
+            run = 0;
+        } else {
+            run += 1;
+            if run == smallest {
+                groups += 1;
+                // skip the gap after it.
+                run = smallest + 1;
+            }
+        }
+    }
+    groups
+}
+
+// number of arrangements of the line unfolded into `copies` copies, modulo `modulus`, without
+// building the unfolded line. Every copy after the first is the joiner followed by the springs,
+// which moves the automaton between the same boundary states each time, so the count is the first
+// copy's states times the transfer matrix of one copy raised to copies - 1.
+// Groups can drift into other copies when a copy has room for more of them than it needs, so only
+// states within the drift bounds for the given number of copies are kept. Lines with room to spare
+// then get more states the more copies there are, while lines with no room stay small no matter how
+// many copies there are. Returns None once there are more than MAX_STATES states, then the table
+// has to be used instead.
+fn count_unfolded_mod(line: &str, copies: usize, joiner: char, modulus: u64) -> Option<u128> {
+    let (chars, nums) = parse_line(line);
+    let modulus_u128 = modulus as u128;
+    if copies == 0 || nums.is_empty() {
+        let valid = copies == 0 || (!chars.contains(&'#') && (copies == 1 || joiner != '#'));
+        return Some(valid as u128 % modulus_u128);
+    }
+    let mut block = vec![joiner];
+    block.extend_from_slice(&chars);
+
+    // at the boundary after copy j, with j between 1 and copies - 1, the groups can't have run
+    // ahead further than the ones the copies so far have room for, or than there are groups left,
+    // and can't be further behind than the copies left have room to catch up on.
+    let m = nums.len();
+    let spare = max_groups(&block, *nums.iter().min().unwrap()).saturating_sub(m);
+    let ahead = (m.min(spare) * (copies - 1)) as isize;
+    let behind = (spare * (copies - 1)) as isize + 1;
+
+    // every boundary state reachable from the first copy, with its transitions.
+    let first = feed(&chars, &nums, (0, 0), modulus);
+    let mut index: HashMap<Boundary, usize> = HashMap::new();
+    let mut states: Vec<Boundary> = Vec::new();
+    let mut transitions: Vec<HashMap<Boundary, u128>> = Vec::new();
+    let mut queue: VecDeque<Boundary> = first.keys().copied().collect();
+    while let Some(state) = queue.pop_front() {
+        if index.contains_key(&state) {
+            continue;
+        }
+        if states.len() == MAX_STATES {
+            return None;
+        }
+        let mut out = feed(&block, &nums, state, modulus);
+        out.retain(|&(d, _), _| -behind <= d && d <= ahead);
+        queue.extend(out.keys().copied());
+        index.insert(state, states.len());
+        states.push(state);
+        transitions.push(out);
+    }
+
+    let n = states.len();
+    let mut transfer = vec![vec![0; n]; n];
+    for (i, out) in transitions.iter().enumerate() {
+        for (state, &count) in out.iter() {
+            transfer[i][index[state]] = count;
+        }
+    }
+    let mut counts = vec![vec![0; n]];
+    for (state, &count) in first.iter() {
+        counts[0][index[state]] = count;
+    }
+    let mut e = copies - 1;
+    while e > 0 {
+        if e & 1 == 1 {
+            counts = mat_mul(&counts, &transfer, modulus);
+        }
+        transfer = mat_mul(&transfer, &transfer, modulus);
+        e >>= 1;
+    }
+
+    // all groups finished, or the last one finished right at the end of the line.
+    let last = nums[nums.len() - 1];
+    let count = [(0, 0), (-1, last)].iter()
+        .filter_map(|s| index.get(s))
+        .fold(0, |acc, &i| (acc + counts[0][i]) % modulus_u128);
+    Some(count)
+}
+
+// the next position at or after `from` where a group of n springs can start, without skipping over
+// a spring that would then not be part of any group.
+fn next_group_start(chars: &[char], offset: usize, from: usize, n: usize) -> Option<usize> {
+    let mut i = from;
+    while i + n <= chars.len() {
+        if chars[offset..i].contains(&'#') {
+            return None;
+        }
+        let fits = chars[i..i+n].iter().all(|c| *c != '.');
+        if fits && (i + n == chars.len() || chars[i+n] != '#') {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+fn render_arrangement(len: usize, nums: &VecDeque<usize>, starts: &[usize]) -> String {
+    let mut out = vec!['.'; len];
+    for (&start, &n) in starts.iter().zip(nums.iter()) {
+        out[start..start+n].iter_mut().for_each(|c| *c = '#');
+    }
+    out.into_iter().collect()
+}
+
+// lazily enumerates every arrangement of springs as a string of '#' and '.', by placing the groups
+// left to right and backtracking.
+struct Arrangements<'a> {
+    chars: &'a [char],
+    nums: &'a VecDeque<usize>,
+    // start position of each group placed so far.
+    starts: Vec<usize>,
+    // where to look for the next position of the group after the placed ones.
+    resume: usize,
+    done: bool,
+}
+
+impl<'a> Arrangements<'a> {
+    fn new(chars: &'a [char], nums: &'a VecDeque<usize>) -> Arrangements<'a> {
+        Arrangements { chars, nums, starts: Vec::new(), resume: 0, done: false }
+    }
+
+    // move the last placed group one further along, returns false when there's nothing left.
+    fn backtrack(&mut self) -> bool {
+        match self.starts.pop() {
+            Some(start) => { self.resume = start + 1; true },
+            None => { self.done = true; false },
+        }
+    }
 }
 
-fn solve(filename: &str, repeat: usize) -> usize { 
+impl<'a> Iterator for Arrangements<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        while !self.done {
+            let k = self.starts.len();
+            let offset = if k == 0 { 0 } else { self.starts[k-1] + self.nums[k-1] + 1 };
+            if k == self.nums.len() {
+                // all groups placed, valid if there are no springs left over.
+                let valid = offset >= self.chars.len() || !self.chars[offset..].contains(&'#');
+                let arrangement = render_arrangement(self.chars.len(), self.nums, &self.starts);
+                self.backtrack();
+                if valid {
+                    return Some(arrangement);
+                }
+                continue;
+            }
+            if offset > self.chars.len() {
+                self.backtrack();
+                continue;
+            }
+            match next_group_start(self.chars, offset, self.resume.max(offset), self.nums[k]) {
+                Some(start) => { self.starts.push(start); self.resume = 0; },
+                None => { self.backtrack(); },
+            }
+        }
+        None
+    }
+}
+
+// small xorshift generator so sampling is reproducible from a seed.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        // the state must never be zero.
+        Rng(seed ^ 0x9E3779B97F4A7C15)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // a random number in 0..n, using 128 more random bits than n has so the modulo bias is at
+    // most 2^-128.
+    fn below(&mut self, n: &BigUint) -> BigUint {
+        let words = (n.bits() as usize + 128).div_ceil(64);
+        let digits = (0..words).flat_map(|_| {
+            let r = self.next_u64();
+            [r as u32, (r >> 32) as u32]
+        }).collect::<Vec<u32>>();
+        BigUint::new(digits) % n
+    }
+}
+
+// pick one arrangement uniformly at random, without enumerating them: each group's position is
+// chosen with probability proportional to the number of ways of placing the remaining groups.
+fn sample_arrangement(chars: &[char], nums: &VecDeque<usize>, rng: &mut Rng) -> Option<String> {
+    let table = ArrangementTable::new(chars, nums);
+
+    let mut remaining = table.get(0, 0);
+    if remaining.is_zero() {
+        return None;
+    }
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    for (k, &n) in nums.iter().enumerate() {
+        let mut pick = rng.below(remaining);
+        let mut from = offset;
+        loop {
+            let start = next_group_start(chars, offset, from, n).unwrap();
+            let ways = table.get(start + n + 1, k + 1);
+            if &pick < ways {
+                starts.push(start);
+                offset = start + n + 1;
+                remaining = ways;
+                break;
+            }
+            pick -= ways;
+            from = start + 1;
+        }
+    }
+    Some(render_arrangement(chars.len(), nums, &starts))
+}
+
+fn solve(filename: &str, copies: usize) -> BigUint { 
     let lines = get_lines(filename);
 
-    let mut count = 0;
+    let mut count = BigUint::zero();
     for line in lines {
-        let c = find_arrangements_in_line(&line, repeat);
+        let c = find_arrangements_in_line(&line, copies, '?');
         count += c
     }
 
@@ -104,18 +477,161 @@ fn solve(filename: &str, repeat: usize) -> usize {
 mod tests {
     use super::*;
 
+    // check that an arrangement matches the springs we know about and has the right groups.
+    fn is_valid(arrangement: &str, chars: &[char], nums: &VecDeque<usize>) -> bool {
+        let matches = arrangement.chars().zip(chars.iter()).all(|(a, &c)| c == '?' || a == c);
+        let groups = arrangement.split('.').filter(|g| !g.is_empty()).map(|g| g.len()).collect::<VecDeque<usize>>();
+        arrangement.len() == chars.len() && matches && &groups == nums
+    }
+
+    #[test]
+    fn test_arrangements() {
+        let (chars, nums) = parse_line("???.### 1,1,3");
+        assert_eq!(Arrangements::new(&chars, &nums).collect::<Vec<String>>(), vec!["#.#.###"]);
+
+        let (chars, nums) = parse_line(".??..??...?##. 1,1,3");
+        let all = Arrangements::new(&chars, &nums).collect::<Vec<String>>();
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[0], ".#...#....###.");
+        assert_eq!(all[3], "..#...#...###.");
+
+        for line in get_lines("files/day12_sample.txt").iter().chain(get_lines("files/day12_input.txt").iter().take(50)) {
+            let (chars, nums) = parse_line(line);
+            let all = Arrangements::new(&chars, &nums).collect::<std::collections::HashSet<String>>();
+            assert_eq!(BigUint::from(all.len()), find_arrangements_in_line(line, 1, '?'));
+            assert!(all.iter().all(|a| is_valid(a, &chars, &nums)));
+        }
+    }
+
+    #[test]
+    fn test_sample_arrangement() {
+        let mut rng = Rng::new(2023);
+
+        // every arrangement should come up about equally often.
+        let (chars, nums) = parse_line("?###???????? 3,2,1");
+        let all = Arrangements::new(&chars, &nums).collect::<Vec<String>>();
+        let mut seen: HashMap<String, u128> = HashMap::new();
+        for _ in 0..10000 {
+            *seen.entry(sample_arrangement(&chars, &nums, &mut rng).unwrap()).or_insert(0) += 1;
+        }
+        assert_eq!(seen.len(), all.len());
+        assert!(seen.values().all(|&n| n > 800 && n < 1200));
+
+        // too many to enumerate, but we can still pick one.
+        let (chars, nums) = unfold_line("?###???????? 3,2,1", 5, '?');
+        for _ in 0..10 {
+            assert!(is_valid(&sample_arrangement(&chars, &nums, &mut rng).unwrap(), &chars, &nums));
+        }
+        let (chars, nums) = parse_line("### 1");
+        assert_eq!(sample_arrangement(&chars, &nums, &mut rng), None);
+    }
+
+    #[test]
+    fn test_unfold() {
+        let (chars, nums) = unfold_line(".# 1", 3, '.');
+        assert_eq!(chars.iter().collect::<String>(), ".#..#..#");
+        assert_eq!(nums, VecDeque::from([1, 1, 1]));
+        assert_eq!(find_arrangements_in_line("?# 1", 2, '#'), BigUint::from(0u64));
+        assert_eq!(find_arrangements_in_line("??? 1", 2, '.'), BigUint::from(11u64));
+        assert_eq!(find_arrangements_in_line("??? 1", 2, '?'), BigUint::from(15u64));
+
+        // the transfer matrix agrees with the table on every line of the input.
+        let p = (1 << 61) - 1;
+        let lines = get_lines("files/day12_input.txt");
+        for line in lines.iter() {
+            assert_eq!(BigUint::from(count_unfolded_mod(line, 5, '?', p).unwrap()), find_arrangements_in_line(line, 5, '?') % p);
+        }
+        for line in lines.iter().take(100) {
+            for joiner in ['.', '#'] {
+                for copies in 0..6 {
+                    assert_eq!(BigUint::from(count_unfolded_mod(line, copies, joiner, p).unwrap()), find_arrangements_in_line(line, copies, joiner) % p);
+                }
+            }
+        }
+
+        // ten ways for the first copy and fifteen for each one after.
+        let copies = 1_000_000_000_000;
+        let mut expected = 10;
+        let mut base = 15;
+        let mut e = copies - 1;
+        while e > 0 {
+            if e & 1 == 1 {
+                expected = expected * base % p as u128;
+            }
+            base = base * base % p as u128;
+            e >>= 1;
+        }
+        assert_eq!(count_unfolded_mod("?###???????? 3,2,1", copies, '?', p), Some(expected));
+        assert_eq!(count_unfolded_mod("???.### 1,1,3", copies, '?', p), Some(1));
+        // a single group can end up in any copy, so the states grow with the copies.
+        for copies in [10, 30] {
+            assert_eq!(BigUint::from(count_unfolded_mod("??????? 1", copies, '?', p).unwrap()), find_arrangements_in_line("??????? 1", copies, '?') % p);
+        }
+        // but not without bound: a trillion copies would need trillions of states.
+        assert_eq!(count_unfolded_mod("??????? 1", copies, '?', p), None);
+    }
+
+    #[test]
+    fn test_speedup() {
+        let lines = get_lines("files/day12_input.txt");
+        // the recursive count is a usize, so further unfolded only time the lines that fit.
+        let fits = |line: &&String| find_arrangements_in_line(line, 10, '?') <= BigUint::from(usize::MAX);
+        for (copies, lines) in [(5, lines.iter().collect::<Vec<&String>>()), (10, lines.iter().filter(fits).collect())] {
+            let unfolded = lines.iter().map(|line| unfold_line(line, copies, '?')).collect::<Vec<_>>();
+
+            let start = std::time::Instant::now();
+            let mut recursive = BigUint::zero();
+            for (chars, nums) in unfolded.iter() {
+                let mut cache: HashMap<(usize, usize), usize> = HashMap::new();
+                recursive += find_all_arrangements(chars, 0, nums, 0, &mut cache);
+            }
+            let recursive_time = start.elapsed();
+
+            let start = std::time::Instant::now();
+            let mut table = BigUint::zero();
+            for (chars, nums) in unfolded.iter() {
+                table += count_arrangements(chars, nums);
+            }
+            let table_time = start.elapsed();
+            println!("unfold {} ({} lines): recursive {:?}, table {:?}", copies, lines.len(), recursive_time, table_time);
+            assert_eq!(recursive, table);
+        }
+    }
+
+    #[test]
+    fn test_large_counts() {
+        // the table agrees with the recursive count while that still fits in a usize.
+        for line in get_lines("files/day12_input.txt").iter() {
+            let (chars, nums) = unfold_line(line, 5, '?');
+            let mut cache: HashMap<(usize, usize), usize> = HashMap::new();
+            let recursive = find_all_arrangements(&chars, 0, &nums, 0, &mut cache);
+            assert_eq!(count_arrangements(&chars, &nums), BigUint::from(recursive));
+        }
+
+        // ten ways for the first copy and fifteen for each one after, well past a u128.
+        let count = find_arrangements_in_line("?###???????? 3,2,1", 40, '?');
+        assert_eq!(count, BigUint::from(10u32) * BigUint::from(15u32).pow(39));
+        assert!(count > BigUint::from(u128::MAX));
+
+        // some lines of the input overflow a u128 too.
+        let overflowing = get_lines("files/day12_input.txt").iter()
+            .filter(|line| find_arrangements_in_line(line, 20, '?') > BigUint::from(u128::MAX))
+            .count();
+        assert_eq!(overflowing, 23);
+    }
+
     #[test]
     fn test() {
-        assert_eq!(find_arrangements_in_line("?...??.#.????##??? 1,1,1,7", 0), 9);
-        assert_eq!(find_arrangements_in_line("??.???#?????#???. 1,13", 0), 2);
-        assert_eq!(find_arrangements_in_line("?###???????? 3,2,1", 0), 10);
-        assert_eq!(find_arrangements_in_line("????#??.???#????? 2,8", 0), 4);
-        assert_eq!(find_arrangements_in_line("???.### 1,1,3", 4), 1);
-        assert_eq!(find_arrangements_in_line("?###???????? 3,2,1", 4), 506250);
+        assert_eq!(find_arrangements_in_line("?...??.#.????##??? 1,1,1,7", 1, '?'), BigUint::from(9u64));
+        assert_eq!(find_arrangements_in_line("??.???#?????#???. 1,13", 1, '?'), BigUint::from(2u64));
+        assert_eq!(find_arrangements_in_line("?###???????? 3,2,1", 1, '?'), BigUint::from(10u64));
+        assert_eq!(find_arrangements_in_line("????#??.???#????? 2,8", 1, '?'), BigUint::from(4u64));
+        assert_eq!(find_arrangements_in_line("???.### 1,1,3", 5, '?'), BigUint::from(1u64));
+        assert_eq!(find_arrangements_in_line("?###???????? 3,2,1", 5, '?'), BigUint::from(506250u64));
          
-        assert_eq!(solve("files/day12_sample.txt", 0), 21);
-        assert_eq!(solve("files/day12_input.txt", 0), 7090);
-        assert_eq!(solve("files/day12_sample.txt", 4), 525152);
-        assert_eq!(solve("files/day12_input.txt", 4), 6792010726878);
+        assert_eq!(solve("files/day12_sample.txt", 1), BigUint::from(21u64));
+        assert_eq!(solve("files/day12_input.txt", 1), BigUint::from(7090u64));
+        assert_eq!(solve("files/day12_sample.txt", 5), BigUint::from(525152u64));
+        assert_eq!(solve("files/day12_input.txt", 5), BigUint::from(6792010726878u64));
     }
 }
\ No newline at end of file
@@ -1,7 +1,12 @@
 use crate::common::get_lines;
+use bitvec::prelude::*;
+
+// a row or column of a pattern, one bit per cell, set if it's a '#'. Unused bits in the last word
+// are always zero, so lines can be compared a word at a time.
+type Line = BitVec<u64, Lsb0>;
 
 // count set bits using brian kernighan's algorithm
-fn count_set_bits(n: usize) -> usize {
+fn count_set_bits(n: u64) -> usize {
     let mut count = 0;
     let mut n = n;
     while n > 0 {
@@ -11,153 +16,329 @@ fn count_set_bits(n: usize) -> usize {
     count
 }
 
-fn is_valid_reflection(nums: &Vec<usize>, i: usize) -> bool {
+// number of cells that differ between two lines of the same length.
+fn count_differences(a: &Line, b: &Line) -> usize {
+    a.as_raw_slice().iter().zip(b.as_raw_slice()).map(|(x, y)| count_set_bits(x ^ y)).sum()
+}
+
+// count # of smudges (bit flips) needed to make a valid reflection between
+// lines i and i + 1, giving up once there are more than `limit`.
+fn count_smudges(nums: &[Line], i: usize, limit: usize) -> usize {
     let mut left: isize = i as isize;
     let mut right: isize = i as isize + 1;
-    while left >= 0 && right < nums.len() as isize {
-        if nums[left as usize] != nums[right as usize] {
-            return false;
-        }
+    let mut count = 0;
+    while left >= 0 && right < nums.len() as isize && count <= limit {
+        count += count_differences(&nums[left as usize], &nums[right as usize]);
         left -= 1;
         right += 1;
     }
-    true
+    count
 }
 
-fn find_reflection_nums(nums: &Vec<usize>) -> usize {
-    let mut i = 0;
-    let mut j = i + 1;
-    while j < nums.len() {
-        if nums[i] == nums[j] && is_valid_reflection(nums, i) {
-            return j
+// convert each row / col into a bit vector
+fn rows_and_cols(map: &[Vec<char>]) -> (Vec<Line>, Vec<Line>) {
+    let n_rows = map.len();
+    let n_cols = map[0].len();
+    let mut rows: Vec<Line> = vec![bitvec![u64, Lsb0; 0; n_cols]; n_rows];
+    let mut cols: Vec<Line> = vec![bitvec![u64, Lsb0; 0; n_rows]; n_cols];
+
+    for (i, row) in map.iter().enumerate() {
+        for (j, c) in row.iter().enumerate() {
+            if *c == '#' {
+                rows[i].set(j, true);
+                cols[j].set(i, true);
+            }
         }
-        i += 1;
-        j += 1;
     }
-    0
+    (rows, cols)
 }
 
-// count # of smudges (bit flips) needed to make a valid reflection starting
-// at index i.
-fn count_smudges(nums: &Vec<usize>, i: usize) -> usize {
-    let mut left: isize = i as isize;
-    let mut right: isize = i as isize + 1;
-    let mut count = 0;
-    while left >= 0 && right < nums.len() as isize {
-        let xor = nums[left as usize] ^ nums[right as usize];
-        count += count_set_bits(xor);
-        left -= 1;
-        right += 1;
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Axis {
+    // a mirror line between two rows.
+    Horizontal,
+    // a mirror line between two columns.
+    Vertical,
+}
+
+// a cell that doesn't match its mirror image, as the (row, col) of the cell and of its image.
+type Smudge = ((usize, usize), (usize, usize));
+
+// a mirror line right before row / column `index`, and the cells that don't match.
+#[derive(Debug, PartialEq)]
+struct Reflection {
+    axis: Axis,
+    index: usize,
+    smudges: Vec<Smudge>,
+}
+
+impl Reflection {
+    // the puzzle's summary: columns left of a vertical line, or 100 times the rows above a
+    // horizontal one.
+    fn summary(&self) -> usize {
+        match self.axis {
+            Axis::Horizontal => 100 * self.index,
+            Axis::Vertical => self.index,
+        }
     }
-    count
 }
 
-// return smudges needed to make a reflection work for all possible rows in 
-// nums
-fn find_reflection_smudges(nums: &Vec<usize>) -> Vec<usize> {
-    let mut i = 0;
-    let mut j = i + 1;
-    let mut reflections: Vec<usize> = Vec::new();
-    while j < nums.len() {
-        let smudges = count_smudges(nums, i);
-        reflections.push(smudges);
-        i += 1;
-        j += 1;
+// all mirror lines between nums that are off by exactly `smudges` cells, with the cells that
+// differ as (line, position) pairs.
+fn find_line_reflections(nums: &[Line], smudges: usize) -> Vec<(usize, Vec<Smudge>)> {
+    let mut reflections = Vec::new();
+    for i in 0..nums.len().saturating_sub(1) {
+        if count_smudges(nums, i, smudges) != smudges {
+            continue;
+        }
+        let mut cells = Vec::new();
+        for (left, right) in (0..=i).rev().zip(i + 1..nums.len()) {
+            let diff = nums[left].clone() ^ &nums[right];
+            cells.extend(diff.iter_ones().map(|p| ((left, p), (right, p))));
+        }
+        reflections.push((i + 1, cells));
     }
     reflections
 }
 
-// convert each row / col into a usize
-fn rows_and_cols(map: &Vec<Vec<char>>) -> (Vec<usize>, Vec<usize>) {
-    let mut rows: Vec<usize> = Vec::new();
-    let mut cols: Vec<usize> = Vec::new();
+// every mirror line of the pattern, horizontal ones first, that needs exactly `smudges` cells
+// flipped to be a perfect reflection.
+fn find_reflections(map: &[Vec<char>], smudges: usize) -> Vec<Reflection> {
+    // encode each row and column as bits, where each bit indicates 1 if #, 0
+    // otherwise.
+    let (rows, cols) = rows_and_cols(map);
 
-    for row in map {
-        let mut r = 0;
-        for c in row {
-            r <<= 1;
-            if *c == '#' {
-                r |= 1;
-            }
-        }
-        rows.push(r);
+    let mut reflections = Vec::new();
+    for (index, cells) in find_line_reflections(&rows, smudges) {
+        reflections.push(Reflection { axis: Axis::Horizontal, index, smudges: cells });
+    }
+    // column lines are (col, row) so flip them around.
+    for (index, cells) in find_line_reflections(&cols, smudges) {
+        let smudges = cells.into_iter().map(|((c1, r1), (c2, r2))| ((r1, c1), (r2, c2))).collect();
+        reflections.push(Reflection { axis: Axis::Vertical, index, smudges });
     }
+    reflections
+}
 
-    let n_rows = rows.len();
-    let n_cols = map[0].len();
+// clean the smudges of a reflection by copying each cell onto its mirror image.
+fn fix_smudges(map: &mut [Vec<char>], reflection: &Reflection) {
+    for &((r1, c1), (r2, c2)) in reflection.smudges.iter() {
+        map[r2][c2] = map[r1][c1];
+    }
+}
 
-    for i in 0..n_cols {
-        let mut c = 0;
-        for j in 0..n_rows {
-            c <<= 1;
-            if map[j][i] == '#' {
-                c |= 1;
-            }
-        }
-        cols.push(c);
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum SymmetryKind {
+    // the same after turning it 180 degrees.
+    Rotation,
+    // mirrored along the top-left to bottom-right diagonal, i.e. the same as its transpose.
+    Diagonal,
+    // mirrored along the top-right to bottom-left diagonal.
+    AntiDiagonal,
+}
+
+// a square sub-pattern with a symmetry.
+#[derive(Debug, PartialEq)]
+struct Symmetry {
+    kind: SymmetryKind,
+    top: usize,
+    left: usize,
+    size: usize,
+}
+
+impl Symmetry {
+    // (row, col) of the centre of the square, halfway between cells when the size is even.
+    fn centre(&self) -> (f64, f64) {
+        let half = (self.size - 1) as f64 / 2.0;
+        (self.top as f64 + half, self.left as f64 + half)
     }
-    (rows, cols)
 }
 
-fn find_reflections(map: &Vec<Vec<char>>) -> (usize, usize) {
-    // encode each row and column as a usize, where each bit indicates 1 if #, 0
-    // otherwise.
+// whether the size x size square at (top, left) has the symmetry. Row i of the square is compared
+// against row size - 1 - i reversed for a rotation, column i for the diagonal and column
+// size - 1 - i reversed for the anti-diagonal.
+fn is_symmetric(rows: &[Line], cols: &[Line], kind: SymmetryKind, top: usize, left: usize, size: usize) -> bool {
+    (0..size).all(|i| {
+        let row = &rows[top + i][left..left + size];
+        match kind {
+            SymmetryKind::Rotation => {
+                let other = &rows[top + size - 1 - i][left..left + size];
+                row.iter().by_vals().eq(other.iter().by_vals().rev())
+            },
+            SymmetryKind::Diagonal => *row == cols[left + i][top..top + size],
+            SymmetryKind::AntiDiagonal => {
+                let other = &cols[left + size - 1 - i][top..top + size];
+                row.iter().by_vals().eq(other.iter().by_vals().rev())
+            },
+        }
+    })
+}
+
+// the largest symmetric square around every centre, for squares of at least min_size. Shrinking
+// a symmetric square around its centre keeps it symmetric, so each centre only needs to grow
+// until the symmetry breaks or the square hits the edge.
+fn find_symmetries(map: &[Vec<char>], min_size: usize) -> Vec<Symmetry> {
     let (rows, cols) = rows_and_cols(map);
+    let n_rows = rows.len();
+    let n_cols = cols.len();
 
-    // find all reflections of rows and cols
-    (find_reflection_nums(&cols), find_reflection_nums(&rows))
+    let mut symmetries = Vec::new();
+    for kind in [SymmetryKind::Rotation, SymmetryKind::Diagonal, SymmetryKind::AntiDiagonal] {
+        // centres in half cells, on a cell for odd sizes and on a corner for even ones.
+        for r2 in 0..2 * n_rows - 1 {
+            for c2 in (r2 % 2..2 * n_cols - 1).step_by(2) {
+                let mut best = None;
+                let mut size = r2 % 2 + 1;
+                while size <= r2 + 1 && size <= c2 + 1 {
+                    let (top, left) = ((r2 + 1 - size) / 2, (c2 + 1 - size) / 2);
+                    if top + size > n_rows || left + size > n_cols
+                        || !is_symmetric(&rows, &cols, kind, top, left, size) {
+                        break;
+                    }
+                    best = Some(Symmetry { kind, top, left, size });
+                    size += 2;
+                }
+                if let Some(symmetry) = best.filter(|s| s.size >= min_size) {
+                    symmetries.push(symmetry);
+                }
+            }
+        }
+    }
+    symmetries
 }
 
-fn part1(filename: &str) -> usize {
+// the patterns in a file, separated by empty lines.
+fn parse_patterns(filename: &str) -> Vec<Vec<Vec<char>>> {
     let lines = get_lines(filename);
 
+    let mut patterns = Vec::new();
     let mut map: Vec<Vec<char>> = Vec::new();
-    let mut sum = 0;
     for line in lines {
         if line.is_empty() {
-            let (vertical, horizontal) = find_reflections(&map);
-            println!("vertical: {}, horizontal: {}", vertical, horizontal);
-            sum += vertical + horizontal*100;
-            map.clear();
-        } else { 
-            let chars: Vec<char> = line.chars().collect();
-            map.push(chars);
+            if !map.is_empty() {
+                patterns.push(std::mem::take(&mut map));
+            }
+        } else {
+            map.push(line.chars().collect());
         }
     }
-    sum
+    if !map.is_empty() {
+        patterns.push(map);
+    }
+    patterns
+}
+
+fn solve(filename: &str, smudges: usize) -> usize {
+    parse_patterns(filename).iter()
+        .flat_map(|map| find_reflections(map, smudges))
+        .map(|r| r.summary())
+        .sum()
+}
+
+fn part1(filename: &str) -> usize {
+    solve(filename, 0)
 }
 
 fn part2(filename: &str) -> usize {
-    let lines = get_lines(filename);
+    solve(filename, 1)
+}
 
-    let mut map: Vec<Vec<char>> = Vec::new();
-    let mut sum = 0;
-    for line in lines {
-        if line.is_empty() {
-            let (rows, cols)  = rows_and_cols(&map);
-            let horizontal_smudges = find_reflection_smudges(&rows);
-            let vertical_smudges = find_reflection_smudges(&cols);
-
-            // find the index where only 1 smudge is needed
-            let horizontal = horizontal_smudges.iter().position(|&x| x == 1);
-            let vertical = vertical_smudges.iter().position(|&x| x == 1);
-            if let Some(offset) = horizontal {
-                sum += 100 * (offset + 1);
+mod tests {
+    use super::*;
+
+    // a random pattern of the given size, with its columns mirrored about `mirror`.
+    fn mirrored_pattern(n_rows: usize, n_cols: usize, mirror: usize, seed: u64) -> Vec<Vec<char>> {
+        let mut state = seed;
+        let mut map = vec![vec!['.'; n_cols]; n_rows];
+        for row in map.iter_mut() {
+            for c in row.iter_mut() {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                if state >> 63 == 1 {
+                    *c = '#';
+                }
             }
-            if let Some(offset) = vertical {
-                sum += offset + 1;
+            for k in 0..mirror.min(n_cols - mirror) {
+                row[mirror + k] = row[mirror - 1 - k];
             }
-            map.clear();
-        } else { 
-            let chars: Vec<char> = line.chars().collect();
-            map.push(chars);
         }
+        map
     }
-    sum
-}
 
-mod tests {
-    use super::*;
+    fn transpose(map: &[Vec<char>]) -> Vec<Vec<char>> {
+        (0..map[0].len()).map(|j| map.iter().map(|row| row[j]).collect()).collect()
+    }
+
+    #[test]
+    fn test_wide() {
+        let map = mirrored_pattern(9, 100, 70, 13);
+        let summaries = |map: &[Vec<char>]| find_reflections(map, 0).iter().map(|r| r.summary()).collect::<Vec<usize>>();
+        assert_eq!(summaries(&map), vec![70]);
+        assert_eq!(summaries(&transpose(&map)), vec![7000]);
+
+        // the rows only differ in the first column, which used to be shifted out.
+        let mut map = vec![vec!['.'; 80]; 2];
+        map[0][0] = '#';
+        assert!(find_reflections(&map, 0).iter().all(|r| r.axis == Axis::Vertical));
+        let one = find_reflections(&map, 1);
+        assert_eq!(one[0], Reflection { axis: Axis::Horizontal, index: 1, smudges: vec![((0, 0), (1, 0))] });
+    }
+
+    #[test]
+    fn test_smudges() {
+        let patterns = parse_patterns("files/day13_sample.txt");
+        let reflections = find_reflections(&patterns[0], 1);
+        assert_eq!(reflections, vec![Reflection { axis: Axis::Horizontal, index: 3, smudges: vec![((0, 0), (5, 0))] }]);
+        let mut fixed = patterns[0].clone();
+        fix_smudges(&mut fixed, &reflections[0]);
+        assert!(find_reflections(&fixed, 0).iter().any(|r| r.axis == Axis::Horizontal && r.index == 3));
+
+        let reflections = find_reflections(&patterns[1], 1);
+        assert_eq!(reflections, vec![Reflection { axis: Axis::Horizontal, index: 1, smudges: vec![((0, 4), (1, 4))] }]);
+
+        // more smudges give more lines, each off by exactly that many cells.
+        for k in 0..4 {
+            for map in patterns.iter() {
+                for r in find_reflections(map, k) {
+                    assert_eq!(r.smudges.len(), k);
+                    assert!(r.smudges.iter().all(|&((r1, c1), (r2, c2))| map[r1][c1] != map[r2][c2]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_symmetries() {
+        let map = parse_patterns("files/day13_sample.txt");
+        let rotated = vec!["##.".chars().collect(), "...".chars().collect(), ".##".chars().collect()];
+        assert_eq!(find_symmetries(&rotated, 3), vec![Symmetry { kind: SymmetryKind::Rotation, top: 0, left: 0, size: 3 }]);
+        let diagonal = vec!["##.".chars().collect(), "#..".chars().collect(), "...".chars().collect()];
+        assert_eq!(find_symmetries(&diagonal, 3), vec![Symmetry { kind: SymmetryKind::Diagonal, top: 0, left: 0, size: 3 }]);
+
+        // an even sized square is centred on a corner.
+        let map2 = vec!["#..".chars().collect(), ".#.".chars().collect(), ".##".chars().collect()];
+        let found = find_symmetries(&map2, 2);
+        let anti = Symmetry { kind: SymmetryKind::AntiDiagonal, top: 1, left: 1, size: 2 };
+        assert_eq!(anti.centre(), (1.5, 1.5));
+        assert!(found.contains(&anti));
+
+        // brute force check of everything found in the sample.
+        for pattern in map.iter() {
+            for s in find_symmetries(pattern, 2) {
+                let cell = |r: usize, c: usize| pattern[s.top + r][s.left + c];
+                let n = s.size;
+                for r in 0..n {
+                    for c in 0..n {
+                        let image = match s.kind {
+                            SymmetryKind::Rotation => cell(n - 1 - r, n - 1 - c),
+                            SymmetryKind::Diagonal => cell(c, r),
+                            SymmetryKind::AntiDiagonal => cell(n - 1 - c, n - 1 - r),
+                        };
+                        assert_eq!(cell(r, c), image);
+                    }
+                }
+            }
+        }
+    }
 
     #[test]
     fn test() {
@@ -1,7 +1,57 @@
-use crate::common::get_lines;
+use crate::common::{cycle_index, find_cycle, get_lines};
+use bitvec::prelude::*;
 
-// do the dumb thing to move the rocks.
-fn tilt_north(map: &mut Vec<Vec<char>>) -> bool {
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Dir {
+    North,
+    West,
+    South,
+    East,
+}
+
+// (row, col) of the k-th cell of a line counting from the edge the rocks roll towards. Lines are
+// columns when tilting north or south and rows otherwise.
+fn cell(dir: Dir, line: usize, k: usize, n_rows: usize, n_cols: usize) -> (usize, usize) {
+    match dir {
+        Dir::North => (k, line),
+        Dir::South => (n_rows - 1 - k, line),
+        Dir::West => (line, k),
+        Dir::East => (line, n_cols - 1 - k),
+    }
+}
+
+// roll every round rock as far as it goes in one pass per line: walking away from the edge, keep
+// track of the next free slot, which is right after the edge, a cube rock or the last rock dropped.
+fn tilt(map: &mut [Vec<char>], dir: Dir) {
+    let n_rows = map.len();
+    let n_cols = map[0].len();
+    let (n_lines, len) = match dir {
+        Dir::North | Dir::South => (n_cols, n_rows),
+        Dir::West | Dir::East => (n_rows, n_cols),
+    };
+    for line in 0..n_lines {
+        let mut free = 0;
+        for k in 0..len {
+            let (r, c) = cell(dir, line, k, n_rows, n_cols);
+            match map[r][c] {
+                '#' => free = k + 1,
+                'O' => {
+                    if free != k {
+                        let (fr, fc) = cell(dir, line, free, n_rows, n_cols);
+                        map[fr][fc] = 'O';
+                        map[r][c] = '.';
+                    }
+                    free += 1;
+                },
+                _ => {},
+            }
+        }
+    }
+}
+
+// do the dumb thing to move the rocks, one step at a time. Kept to compare against tilt.
+#[allow(dead_code)]
+fn tilt_north(map: &mut [Vec<char>]) -> bool {
     
     let mut moved = false;
     for i in 1..map.len() {
@@ -16,7 +66,8 @@ fn tilt_north(map: &mut Vec<Vec<char>>) -> bool {
     moved
 }
 
-fn tilt_south(map: &mut Vec<Vec<char>>) -> bool {
+#[allow(dead_code)]
+fn tilt_south(map: &mut [Vec<char>]) -> bool {
     
     let mut moved = false;
     for i in (0..=map.len()-2).rev() {
@@ -31,7 +82,8 @@ fn tilt_south(map: &mut Vec<Vec<char>>) -> bool {
     moved
 }
 
-fn tilt_east(map: &mut Vec<Vec<char>>) -> bool {
+#[allow(dead_code)]
+fn tilt_east(map: &mut [Vec<char>]) -> bool {
     let mut moved = false;
     for i in 0..map.len() {
         for j in (0..=map[i].len()-2).rev() {
@@ -45,7 +97,8 @@ fn tilt_east(map: &mut Vec<Vec<char>>) -> bool {
     moved
 }
 
-fn tilt_west(map: &mut Vec<Vec<char>>) -> bool {
+#[allow(dead_code)]
+fn tilt_west(map: &mut [Vec<char>]) -> bool {
     let mut moved = false;
     for i in 0..map.len() {
         for j in 1..map[i].len() {
@@ -69,75 +122,377 @@ fn print_map(map: &Vec<Vec<char>>) {
     println!();
 }
 
-fn compute_load(map: &Vec<Vec<char>>) -> usize {
-    // count rocks for each row
+// load on the beams along the given edge: each round rock counts its distance from the opposite
+// edge, so a rock right against the edge counts as many as there are rows or columns.
+fn compute_load(map: &[Vec<char>], edge: Dir) -> usize {
+    let n_rows = map.len();
+    let n_cols = map[0].len();
     let mut sum = 0;
-    for (row_idx, row) in map.iter().enumerate() {
-        let mut rocks = 0;
-        for c in row {
-            if *c == 'O' {
-                rocks += 1;
+    for (r, row) in map.iter().enumerate() {
+        for (c, ch) in row.iter().enumerate() {
+            if *ch == 'O' {
+                sum += match edge {
+                    Dir::North => n_rows - r,
+                    Dir::South => r + 1,
+                    Dir::West => n_cols - c,
+                    Dir::East => c + 1,
+                };
             }
         }
-        let multiplier = map.len() - row_idx;
-        sum += rocks * multiplier;
     }
-
     sum
 }
 
-fn cycle(map: &mut Vec<Vec<char>>) {
-    while tilt_north(map) { }
-    while tilt_west(map) { }
-    while tilt_south(map) { }
-    while tilt_east(map) { }
+// the puzzle's spin cycle.
+const SPIN_CYCLE: [Dir; 4] = [Dir::North, Dir::West, Dir::South, Dir::East];
+
+// tilt in each direction of the program, in order.
+fn run(map: &mut [Vec<char>], program: &[Dir], tilt: fn(&mut [Vec<char>], Dir)) {
+    for &dir in program {
+        tilt(map, dir);
+    }
 }
 
-fn part1(filename: &str) -> usize { 
+// tilt the same way as tilt, but one step at a time. Kept to compare against tilt.
+#[allow(dead_code)]
+fn tilt_by_steps(map: &mut [Vec<char>], dir: Dir) {
+    let step = match dir {
+        Dir::North => tilt_north,
+        Dir::West => tilt_west,
+        Dir::South => tilt_south,
+        Dir::East => tilt_east,
+    };
+    while step(map) { }
+}
+
+fn parse_map(filename: &str) -> Vec<Vec<char>> {
     let lines = get_lines(filename);
 
     let mut map: Vec<Vec<char>> = Vec::new();
     for line in lines {
         map.push(line.chars().collect());
     }
+    map
+}
 
-    while tilt_north(&mut map) { }
+fn part1(filename: &str) -> usize { 
+    let mut map = parse_map(filename);
 
-    compute_load(&map)
+    tilt(&mut map, Dir::North);
+
+    compute_load(&map, Dir::North)
 }
 
 fn part2(filename: &str) -> usize { 
-    let lines = get_lines(filename);
+    solve(filename, &SPIN_CYCLE, 1000000000, Dir::North)
+}
 
-    let mut map: Vec<Vec<char>> = Vec::new();
-    for line in lines {
-        map.push(line.chars().collect());
+// load on the given edge after running the program `iterations` times.
+fn solve(filename: &str, program: &[Dir], iterations: usize, edge: Dir) -> usize {
+    let map = run_program(parse_map(filename), program, iterations, tilt);
+    compute_load(&map, edge)
+}
+
+// positions of the round rocks, which is all that changes between cycles.
+fn round_rocks(map: &[Vec<char>]) -> Vec<u32> {
+    let n_cols = map[0].len();
+    let mut rocks = Vec::new();
+    for (i, row) in map.iter().enumerate() {
+        for (j, c) in row.iter().enumerate() {
+            if *c == 'O' {
+                rocks.push((i * n_cols + j) as u32);
+            }
+        }
+    }
+    rocks
+}
+
+// the map after running the program `iterations` times, by finding where the maps start repeating.
+fn run_program(map: Vec<Vec<char>>, program: &[Dir], iterations: usize, tilt: fn(&mut [Vec<char>], Dir)) -> Vec<Vec<char>> {
+    let spin = |m: &Vec<Vec<char>>| {
+        let mut m = m.clone();
+        run(&mut m, program, tilt);
+        m
+    };
+    let (mu, lambda) = find_cycle(map.clone(), spin, |m| round_rocks(m));
+
+    let mut map = map;
+    for _ in 0..cycle_index(mu, lambda, iterations) {
+        run(&mut map, program, tilt);
+    }
+    map
+}
+
+type Bits = BitVec<u64, Lsb0>;
+
+// the bits start..end of a bitset's words, one word at a time as (word, mask) pairs.
+fn word_masks(start: usize, end: usize) -> impl Iterator<Item = (usize, u64)> {
+    (start / 64..end.div_ceil(64)).map(move |w| {
+        let lo = start.max(w * 64) - w * 64;
+        let hi = end.min(w * 64 + 64) - w * 64;
+        let mask = if hi - lo == 64 { u64::MAX } else { ((1u64 << (hi - lo)) - 1) << lo };
+        (w, mask)
+    })
+}
+
+// roll the round rocks of one row towards its start or end: in each segment between cube rocks
+// the round ones are counted, cleared and set again packed against one side.
+fn tilt_segments(round: &mut [u64], segments: &[(usize, usize)], towards_start: bool) {
+    for &(start, end) in segments {
+        let mut n = 0;
+        for (w, mask) in word_masks(start, end) {
+            n += (round[w] & mask).count_ones() as usize;
+            round[w] &= !mask;
+        }
+        let (from, to) = if towards_start { (start, start + n) } else { (end - n, end) };
+        for (w, mask) in word_masks(from, to) {
+            round[w] |= mask;
+        }
+    }
+}
+
+// roll the round rocks north, or south, a word of each row at a time: the rows closer to the edge
+// have already settled, so every rock of a row keeps moving one row on while the cell ahead of it
+// is free, and stops on the row before the first rock or cube rock in its way.
+fn tilt_rows(round: &mut [Bits], cube: &[Bits], north: bool) {
+    let n = round.len();
+    let row = |i: usize| if north { i } else { n - 1 - i };
+    let mut round = round.iter_mut().map(|r| r.as_raw_mut_slice()).collect::<Vec<&mut [u64]>>();
+    let cube = cube.iter().map(|r| r.as_raw_slice()).collect::<Vec<&[u64]>>();
+    for w in 0..round[0].len() {
+        for i in 1..n {
+            let mut moving = std::mem::take(&mut round[row(i)][w]);
+            let mut at = i;
+            while moving != 0 && at > 0 {
+                let ahead = row(at - 1);
+                let blocked = moving & (round[ahead][w] | cube[ahead][w]);
+                round[row(at)][w] |= blocked;
+                moving &= !blocked;
+                at -= 1;
+            }
+            round[row(at)][w] |= moving;
+        }
+    }
+}
+
+// the round rocks on a platform as one bitset per row, set where there's a rock. This is all that
+// changes when tilting, so it's the only thing cloned and hashed while looking for cycles.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Rocks {
+    round: Vec<Bits>,
+}
+
+impl Rocks {
+    // the words of the rows, enough to tell two states of the same platform apart.
+    fn fingerprint(&self) -> Vec<u64> {
+        self.round.iter().flat_map(|row| row.as_raw_slice().iter().copied()).collect()
+    }
+}
+
+// the size of the platform and its cube rocks as one bitset per row, which never move.
+struct Platform {
+    n_rows: usize,
+    n_cols: usize,
+    cube: Vec<Bits>,
+    // the runs of columns between cube rocks on each row, as start..end.
+    segments: Vec<Vec<(usize, usize)>>,
+}
+
+impl Platform {
+    fn new(map: &[Vec<char>]) -> (Platform, Rocks) {
+        let n_rows = map.len();
+        let n_cols = map[0].len();
+        let mut round = vec![bitvec![u64, Lsb0; 0; n_cols]; n_rows];
+        let mut cube = vec![bitvec![u64, Lsb0; 0; n_cols]; n_rows];
+        for (r, row) in map.iter().enumerate() {
+            for (c, ch) in row.iter().enumerate() {
+                match ch {
+                    'O' => round[r].set(c, true),
+                    '#' => cube[r].set(c, true),
+                    _ => {},
+                }
+            }
+        }
+        let segments = cube.iter().map(|row| {
+            let mut segments = Vec::new();
+            let mut start = 0;
+            for end in row.iter_ones().chain(std::iter::once(n_cols)) {
+                if end > start {
+                    segments.push((start, end));
+                }
+                start = end + 1;
+            }
+            segments
+        }).collect();
+        (Platform { n_rows, n_cols, cube, segments }, Rocks { round })
+    }
+
+    fn tilt(&self, rocks: &mut Rocks, dir: Dir) {
+        match dir {
+            Dir::North | Dir::South => tilt_rows(&mut rocks.round, &self.cube, dir == Dir::North),
+            Dir::West | Dir::East => {
+                for (row, segments) in rocks.round.iter_mut().zip(self.segments.iter()) {
+                    tilt_segments(row.as_raw_mut_slice(), segments, dir == Dir::West);
+                }
+            },
+        }
     }
 
-    let mut maps: Vec<Vec<Vec<char>>> = Vec::new();
+    fn run(&self, rocks: &mut Rocks, program: &[Dir]) {
+        for &dir in program {
+            self.tilt(rocks, dir);
+        }
+    }
 
-    cycle(&mut map);
-    let mut iter = 1;
-    while maps.iter().any(|m| m == &map) == false {
-        maps.push(map.clone());
-        cycle(&mut map);
-        iter += 1;
-        println!("iter: {}", iter);
+    fn load(&self, rocks: &Rocks, edge: Dir) -> usize {
+        let mut sum = 0;
+        for (r, row) in rocks.round.iter().enumerate() {
+            for c in row.iter_ones() {
+                sum += match edge {
+                    Dir::North => self.n_rows - r,
+                    Dir::South => r + 1,
+                    Dir::West => self.n_cols - c,
+                    Dir::East => c + 1,
+                };
+            }
+        }
+        sum
     }
-    let repeating_cycle_start = maps.iter().position(|m| m == &map).unwrap() + 1;
-    let repeats_every = iter - repeating_cycle_start;
-    let rem = (1000000000 - repeating_cycle_start) % repeats_every;
-    println!("repeating cycle start: {}, repeats every {}", repeating_cycle_start, repeats_every);
-    println!("need {} more iterations", rem);
-    for i in 0..rem {
-        cycle(&mut map);
+
+    // back to the text the platform was parsed from, one line per row.
+    fn render(&self, rocks: &Rocks) -> String {
+        let mut out = String::with_capacity(self.n_rows * (self.n_cols + 1));
+        for (round, cube) in rocks.round.iter().zip(self.cube.iter()) {
+            for c in 0..self.n_cols {
+                out.push(if round[c] { 'O' } else if cube[c] { '#' } else { '.' });
+            }
+            out.push('\n');
+        }
+        out
     }
-    compute_load(&map)
+
+    // the rocks after running the program `iterations` times.
+    fn run_program(&self, rocks: Rocks, program: &[Dir], iterations: usize) -> Rocks {
+        let spin = |r: &Rocks| {
+            let mut r = r.clone();
+            self.run(&mut r, program);
+            r
+        };
+        let (mu, lambda) = find_cycle(rocks.clone(), spin, |r| r.fingerprint());
+
+        let mut rocks = rocks;
+        for _ in 0..cycle_index(mu, lambda, iterations) {
+            self.run(&mut rocks, program);
+        }
+        rocks
+    }
+}
+
+fn solve_bitboard(filename: &str, program: &[Dir], iterations: usize, edge: Dir) -> usize {
+    let (platform, rocks) = Platform::new(&parse_map(filename));
+    let rocks = platform.run_program(rocks, program, iterations);
+    platform.load(&rocks, edge)
 }
 
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_tilt() {
+        let map = parse_map("files/day14_input.txt");
+        let mut fast = map.clone();
+        let mut slow = map.clone();
+        for _ in 0..10 {
+            run(&mut fast, &SPIN_CYCLE, tilt);
+            run(&mut slow, &SPIN_CYCLE, tilt_by_steps);
+            assert_eq!(fast, slow);
+        }
+    }
+
+    #[test]
+    fn test_speedup() {
+        let map = parse_map("files/day14_input.txt");
+
+        let start = std::time::Instant::now();
+        let slow = run_program(map.clone(), &SPIN_CYCLE, 1000000000, tilt_by_steps);
+        let slow_time = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let fast = run_program(map.clone(), &SPIN_CYCLE, 1000000000, tilt);
+        let fast_time = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let (platform, rocks) = Platform::new(&map);
+        let rocks = platform.run_program(rocks, &SPIN_CYCLE, 1000000000);
+        let bitboard_time = start.elapsed();
+        println!("billion cycles: step by step {:?}, single pass {:?}, bitboard {:?}", slow_time, fast_time, bitboard_time);
+        assert_eq!(slow, fast);
+        assert_eq!(rocks, Platform::new(&fast).1);
+    }
+
+    #[test]
+    fn test_programs() {
+        let map = parse_map("files/day14_sample.txt");
+        let rocks = round_rocks(&map).len();
+        assert_eq!(compute_load(&map, Dir::North) + compute_load(&map, Dir::South), rocks * (map.len() + 1));
+        assert_eq!(compute_load(&map, Dir::West) + compute_load(&map, Dir::East), rocks * (map[0].len() + 1));
+
+        // one tilt north is part 1, and tilting the same way twice changes nothing.
+        assert_eq!(solve("files/day14_sample.txt", &[Dir::North], 1, Dir::North), 136);
+        assert_eq!(solve("files/day14_sample.txt", &[Dir::North, Dir::North], 5, Dir::North), 136);
+        // with no program the rocks stay put.
+        assert_eq!(solve("files/day14_sample.txt", &[], 1000, Dir::West), compute_load(&map, Dir::West));
+
+        // the cycle shortcut agrees with running every iteration.
+        let program = [Dir::East, Dir::North, Dir::East, Dir::South, Dir::West];
+        let mut slow = map.clone();
+        for iterations in 0..50 {
+            let fast = run_program(map.clone(), &program, iterations, tilt);
+            assert_eq!(fast, slow);
+            for edge in [Dir::North, Dir::West, Dir::South, Dir::East] {
+                assert_eq!(compute_load(&fast, edge), compute_load(&slow, edge));
+            }
+            run(&mut slow, &program, tilt);
+        }
+    }
+
+    #[test]
+    fn test_bitboard() {
+        let text = std::fs::read_to_string("files/day14_sample.txt").unwrap();
+        let map = parse_map("files/day14_sample.txt");
+        let (platform, mut rocks) = Platform::new(&map);
+        assert_eq!(platform.render(&rocks), text);
+
+        // every tilt matches the char map, on a map wider than one word too.
+        let wide = map.iter().map(|row| row.repeat(8)).collect::<Vec<Vec<char>>>();
+        for map in [map, wide] {
+            let (platform, mut rocks) = Platform::new(&map);
+            let mut chars = map.clone();
+            for dir in [Dir::North, Dir::West, Dir::South, Dir::East, Dir::West, Dir::North] {
+                platform.tilt(&mut rocks, dir);
+                tilt(&mut chars, dir);
+                assert_eq!(rocks, Platform::new(&chars).1);
+                assert_eq!(platform.load(&rocks, dir), compute_load(&chars, dir));
+            }
+        }
+
+        platform.run(&mut rocks, &SPIN_CYCLE);
+        assert_eq!(platform.render(&rocks), concat!(
+            ".....#....\n",
+            "....#...O#\n",
+            "...OO##...\n",
+            ".OO#......\n",
+            ".....OOO#.\n",
+            ".O#...O#.#\n",
+            "....O#....\n",
+            "......OOOO\n",
+            "#...O###..\n",
+            "#..OO#....\n"));
+
+        assert_eq!(solve_bitboard("files/day14_sample.txt", &SPIN_CYCLE, 1000000000, Dir::North), 64);
+        assert_eq!(solve_bitboard("files/day14_input.txt", &SPIN_CYCLE, 1000000000, Dir::North), 96317);
+    }
+
     #[test]
     fn test() {
         assert_eq!(part1("files/day14_sample.txt"), 136);
@@ -25,54 +25,149 @@ fn part1(filename: &str) -> usize {
     sum
 }
 
+// the 256 boxes of lenses, each lens going into the box its label hashes to. Removing a lens
+// leaves a tombstone so the others don't have to shift, until the box is more than half
+// tombstones and gets compacted.
+struct LensBoxes {
+    hash: fn(&str) -> usize,
+    boxes: Vec<Box>,
+}
+
+impl LensBoxes {
+    fn new() -> LensBoxes {
+        LensBoxes::with_hash(compute_hash)
+    }
+
+    // boxes using a different hash function, only its value modulo 256 is used.
+    fn with_hash(hash: fn(&str) -> usize) -> LensBoxes {
+        // always have 256 boxes
+        LensBoxes { hash, boxes: vec![Vec::new(); 256] }
+    }
+
+    fn box_index(&self, name: &str) -> usize {
+        (self.hash)(name) % self.boxes.len()
+    }
+
+    fn position(&self, box_idx: usize, name: &str) -> Option<usize> {
+        self.boxes[box_idx].iter().position(|x| matches!(x, Some((n, _)) if n == name))
+    }
+
+    // put a lens in its box, replacing the focal length of a lens with the same name in place.
+    // Returns the focal length that was replaced.
+    fn insert(&mut self, name: &str, focal_length: usize) -> Option<usize> {
+        let box_idx = self.box_index(name);
+        match self.position(box_idx, name) {
+            Some(idx) => {
+                let lens = self.boxes[box_idx][idx].as_mut().unwrap();
+                Some(std::mem::replace(&mut lens.1, focal_length))
+            },
+            None => {
+                self.boxes[box_idx].push(Some((name.to_string(), focal_length)));
+                None
+            },
+        }
+    }
+
+    // take a lens out of its box, returning its focal length.
+    fn remove(&mut self, name: &str) -> Option<usize> {
+        let box_idx = self.box_index(name);
+        let idx = self.position(box_idx, name)?;
+        let (_, focal_length) = self.boxes[box_idx][idx].take().unwrap();
+
+        let tombstones = self.boxes[box_idx].iter().filter(|x| x.is_none()).count();
+        if 2 * tombstones > self.boxes[box_idx].len() {
+            self.boxes[box_idx].retain(|x| x.is_some());
+        }
+        Some(focal_length)
+    }
+
+    fn get(&self, name: &str) -> Option<usize> {
+        let box_idx = self.box_index(name);
+        let idx = self.position(box_idx, name)?;
+        self.boxes[box_idx][idx].as_ref().map(|(_, focal_length)| *focal_length)
+    }
+
+    // the lenses in a box, front to back.
+    fn lenses(&self, box_idx: usize) -> impl Iterator<Item = (&str, usize)> {
+        self.boxes[box_idx].iter().flatten().map(|(name, focal_length)| (name.as_str(), *focal_length))
+    }
+
+    // drop all tombstones.
+    fn compact(&mut self) {
+        for b in self.boxes.iter_mut() {
+            b.retain(|x| x.is_some());
+        }
+    }
+
+    fn focusing_power(&self) -> usize {
+        let mut total_power = 0;
+        for box_idx in 0..self.boxes.len() {
+            for (slot, (_, focal_length)) in self.lenses(box_idx).enumerate() {
+                total_power += (box_idx + 1) * (slot + 1) * focal_length;
+            }
+        }
+        total_power
+    }
+}
+
 fn part2(filename: &str) -> usize { 
     let lines = get_lines(filename);
-    let mut total_power = 0;
-
-    // always have 256 boxes
-    let mut boxes: Vec<Box> = vec![Vec::new(); 256];
+    let mut boxes = LensBoxes::new();
 
     for line in lines {
         for instr in line.split(',') {
             if let Some(idx) = instr.find('=') { 
                 let (name, focal_length) = instr.split_at(idx);
                 let focal_length = focal_length[1..].parse::<usize>().unwrap();
-                let hash = compute_hash(name);
-                // do I really need to clone x here to get the value? 
-                if let Some(found_idx) = boxes[hash].iter().position(|x| x.is_some() && x.clone().unwrap().0 == name) { 
-                    boxes[hash][found_idx].as_mut().unwrap().1 = focal_length;
-                } else { 
-                    boxes[hash].push(Some((name.to_string(), focal_length)));
-                }
+                boxes.insert(name, focal_length);
             } else if let Some(idx) = instr.find('-') {
                 let (name, _) = instr.split_at(idx);
-                let hash = compute_hash(name);
-                if let Some(found_idx) = boxes[hash].iter().position(|x| x.is_some() && x.clone().unwrap().0 == name) { 
-                    boxes[hash][found_idx] = None;
-                }
+                boxes.remove(name);
             }
         }
     }
 
-    // compute power for each box.
-    for (box_idx, b) in boxes.iter().enumerate() { 
-        let mut power = 0; 
-        let mut slot = 1;
-        for item in b { 
-            if let Some((_, focal_length)) = item { 
-                power += (box_idx + 1) * slot * focal_length;
-                slot += 1;
-            }
-        }
-        total_power += power;
-    }
-
-    total_power
+    boxes.focusing_power()
 }
 
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_lens_boxes() {
+        let mut boxes = LensBoxes::new();
+        for (name, focal_length) in [("rn", 1), ("qp", 3), ("cm", 2), ("pc", 4), ("ot", 9), ("ab", 5)] {
+            assert_eq!(boxes.insert(name, focal_length), None);
+        }
+        assert_eq!(boxes.remove("qp"), Some(3));
+        assert_eq!(boxes.remove("qp"), None);
+        assert_eq!(boxes.insert("ot", 7), Some(9));
+        assert_eq!(boxes.get("ot"), Some(7));
+        assert_eq!(boxes.get("qp"), None);
+        assert_eq!(boxes.lenses(0).collect::<Vec<_>>(), vec![("rn", 1), ("cm", 2)]);
+        assert_eq!(boxes.lenses(3).collect::<Vec<_>>(), vec![("pc", 4), ("ot", 7), ("ab", 5)]);
+
+        // "pc" leaves a tombstone, then "ot" leaves the box mostly tombstones so it's compacted.
+        boxes.remove("pc");
+        assert_eq!(boxes.boxes[3].len(), 3);
+        boxes.remove("ot");
+        assert_eq!(boxes.boxes[3], vec![Some(("ab".to_string(), 5))]);
+        boxes.insert("pc", 6);
+        boxes.remove("ab");
+        assert_eq!(boxes.boxes[3].len(), 2);
+        boxes.compact();
+        assert_eq!(boxes.boxes[3], vec![Some(("pc".to_string(), 6))]);
+        assert_eq!(boxes.focusing_power(), 1 + 2 * 2 + 4 * 6);
+
+        // everything in one box, in insertion order.
+        let mut boxes = LensBoxes::with_hash(|_| 0);
+        boxes.insert("a", 1);
+        boxes.insert("b", 2);
+        boxes.insert("c", 3);
+        boxes.remove("b");
+        assert_eq!(boxes.focusing_power(), 1 + 2 * 3);
+    }
+
     #[test]
     fn test() {
         assert_eq!(compute_hash("HASH"), 52);
@@ -1,25 +1,7 @@
 use crate::common::get_lines;
+use crate::polygon::{polygon_area, Position};
 use std::collections::HashSet;
 
-type Position = (isize, isize);
-
-// use the shoelace formula to compute the area of a polygon.
-// https://en.wikipedia.org/wiki/Shoelace_formula
-fn polygon_area(pts: &Vec<Position>, perimeter: isize) -> isize {
-    let mut area = 0;
-    for i in 0..pts.len() {
-        let j = (i + 1) % pts.len();
-        area += (pts[i].1 + pts[j].1) * (pts[i].0 - pts[j].0);
-    }
-    // Pick's theorem states that A = i + b/2 - 1, where b is the boundary.
-    // https://en.wikipedia.org/wiki/Pick%27s_theorem
-    // We want to include the boundary in our area: 
-    // A + b = (i + b/2 - 1) + b/2 + 1
-    //              ^           ^   
-    //         from shoelace   add boundary
-    area.abs() / 2 + perimeter / 2 + 1
-}
-
 fn part1(filename: &str) -> isize {
     let lines = get_lines(filename);
     let mut curr_pos: Position = (0, 0);
@@ -1,6 +1,6 @@
 use crate::common::get_lines;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 #[repr(u8)]
 enum HandType {
     HighCard = 1, 
@@ -19,76 +19,112 @@ impl From<HandType> for u8 {
     }
 }
 
-// store the 5 cards, it's score and the bid.
+impl HandType {
+    // classify a hand from its card counts sorted in descending order, e.g. [3, 2] is a full house.
+    // only the two largest groups matter, so this works for any hand size.
+    fn from_counts(counts: &[usize]) -> HandType {
+        let first = counts.first().copied().unwrap_or(0);
+        let second = counts.get(1).copied().unwrap_or(0);
+        match (first, second) {
+            (f, _) if f >= 5 => HandType::FiveOfAKind,
+            (4, _) => HandType::FourOfAKind,
+            (3, s) if s >= 2 => HandType::FullHouse,
+            (3, _) => HandType::ThreeOfAKind,
+            (2, 2) => HandType::TwoPair,
+            (2, _) => HandType::OnePair,
+            _ => HandType::HighCard,
+        }
+    }
+}
+
+// store the cards, it's score and the bid.
 type HandScore = u32;
 type Bid = usize;
-type Hand = [u8; 5];
-
-fn parse_hand(h: &str, j_is_joker: bool) -> Hand {
-    let mut hand = [0; 5];
-    for (i, c) in h.chars().enumerate() {
-        match c {
-            'A' => hand[i] = 14,
-            'K' => hand[i] = 13,
-            'Q' => hand[i] = 12,
-            'J' => hand[i] = if j_is_joker { 1 } else { 11 },
-            'T' => hand[i] = 10,
-            _ => hand[i] = c.to_digit(10).unwrap() as u8
+type Hand = Vec<u8>;
+
+// describes how a game of camel cards is played: the strength of each card, which cards are wild
+// and how many cards make up a hand.
+struct RankingRules {
+    // cards ordered from strongest to weakest.
+    order: Vec<char>,
+    // cards that act like whatever card makes the hand strongest.
+    wild: Vec<char>,
+    hand_size: usize,
+}
+
+impl RankingRules {
+    fn new(order: &str, wild: &str, hand_size: usize) -> RankingRules {
+        let order = order.chars().collect::<Vec<char>>();
+        let wild = wild.chars().collect::<Vec<char>>();
+        // card values are packed into 4 bits each in the hand score, see calc_hand_score.
+        assert!(order.len() <= 14, "at most 14 distinct cards are supported");
+        assert!(wild.iter().all(|c| order.contains(c)), "wild cards must be part of the card order");
+        assert!(hand_size * 4 + 8 <= HandScore::BITS as usize, "hand is too large to score");
+        RankingRules { order, wild, hand_size }
+    }
+
+    fn standard() -> RankingRules {
+        RankingRules::new("AKQJT98765432", "", 5)
+    }
+
+    // J cards are jokers: wild, but the weakest individual card.
+    fn jokers() -> RankingRules {
+        RankingRules::new("AKQT98765432J", "J", 5)
+    }
+
+    // the weakest card has value 2, so the standard deck keeps its face values (A = 14).
+    fn card_value(&self, c: char) -> u8 {
+        let idx = self.order.iter().position(|&o| o == c)
+            .unwrap_or_else(|| panic!("invalid card {}", c));
+        (self.order.len() - idx + 1) as u8
+    }
+
+    fn card_char(&self, value: u8) -> char {
+        self.order[self.order.len() + 1 - value as usize]
+    }
+
+    fn is_wild(&self, value: u8) -> bool {
+        self.wild.contains(&self.card_char(value))
+    }
+}
+
+fn parse_hand(h: &str, rules: &RankingRules) -> Hand {
+    let hand = h.chars().map(|c| rules.card_value(c)).collect::<Hand>();
+    assert_eq!(hand.len(), rules.hand_size, "invalid hand {}", h);
+    hand
+}
+
+// card counts sorted in descending order, with the wild cards added to the largest group since
+// that always gives the strongest hand.
+fn card_counts(hand: &Hand, rules: &RankingRules) -> Vec<usize> {
+    let mut counts = [0; 16];
+    let mut wilds = 0;
+    for &c in hand.iter() {
+        if rules.is_wild(c) {
+            wilds += 1;
+        } else {
+            counts[c as usize] += 1;
         }
     }
-    return hand as Hand;
+
+    let mut counts = counts.into_iter().filter(|&c| c > 0).collect::<Vec<usize>>();
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    match counts.first_mut() {
+        Some(largest) => *largest += wilds,
+        None => counts.push(wilds),
+    }
+    counts
 }
 
-fn calc_hand_type(hand: Hand) -> HandType { 
-    let mut counts = [0; 15];
-    for c in hand.iter() {
-        counts[*c as usize] += 1;
-    }
-
-    let mut pairs = 0;
-    let mut triples = 0;
-    let mut quads = 0;
-    let mut fives = 0;
-    for c in counts.iter() {
-        if *c == 2 { pairs += 1; }
-        if *c == 3 { triples += 1; }
-        if *c == 4 { quads += 1; }
-        if *c == 5 { fives += 1; }
-    }
-
-    let jokers = counts[1];
-    
-    if jokers == 1 { 
-        if quads == 1 { return HandType::FiveOfAKind; }
-        if triples == 1 { return HandType::FourOfAKind; }
-        if pairs == 2 { return HandType::FullHouse; }
-        if pairs == 1 { return HandType::ThreeOfAKind; }
-        return HandType::OnePair;
-    } else if jokers == 2 { 
-        if triples == 1 { return HandType::FiveOfAKind; }
-        if pairs == 2 { return HandType::FourOfAKind; } // one of these pairs is a joker pair
-        return HandType::ThreeOfAKind;
-    } else if jokers == 3 { 
-        if pairs == 1 { return HandType::FiveOfAKind; }
-        return HandType::FourOfAKind;
-    } else if jokers == 4 { 
-        return HandType::FiveOfAKind;
-    } else { 
-        if fives == 1 { return HandType::FiveOfAKind; }
-        if quads == 1 { return HandType::FourOfAKind; }
-        if triples == 1 && pairs == 1 { return HandType::FullHouse; }
-        if triples == 1 { return HandType::ThreeOfAKind; }
-        if pairs == 2 { return HandType::TwoPair; }
-        if pairs == 1 { return HandType::OnePair; }
-        return HandType::HighCard;
-    }
-}
-
-// 5 cards * 4 bits = 20 bits to keep track of card ordering. 
+fn calc_hand_type(hand: &Hand, rules: &RankingRules) -> HandType { 
+    HandType::from_counts(&card_counts(hand, rules))
+}
+
+// 4 bits per card to keep track of card ordering. 
 // 8 bits for the HandType. 
 // This score can be used to sort the hands in the game.
-fn calc_hand_score(hand: Hand) -> HandScore {
-    let mut score = calc_hand_type(hand) as HandScore;
+fn calc_hand_score(hand: &Hand, rules: &RankingRules) -> HandScore {
+    let mut score = calc_hand_type(hand, rules) as HandScore;
     for c in hand.iter() {
         score = (score << 4) | *c as HandScore;
     }
@@ -96,33 +132,82 @@ fn calc_hand_score(hand: Hand) -> HandScore {
     score
 }
 
-fn solve(filename: &str, with_joker: bool) -> usize { 
+// the card the wild cards in a hand stand in for, if there are any: the most common other card,
+// preferring the stronger card on ties. A hand of only wild cards becomes the strongest card.
+fn wild_substitute(hand: &Hand, rules: &RankingRules) -> Option<char> {
+    if !hand.iter().any(|&c| rules.is_wild(c)) {
+        return None;
+    }
+    let mut counts = [0; 16];
+    for &c in hand.iter().filter(|&&c| !rules.is_wild(c)) {
+        counts[c as usize] += 1;
+    }
+    let best = (0..counts.len()).filter(|&v| counts[v] > 0).max_by_key(|&v| (counts[v], v));
+    match best {
+        Some(v) => Some(rules.card_char(v as u8)),
+        None => Some(rules.order[0]),
+    }
+}
+
+// everything that went into scoring a single hand.
+#[derive(Debug)]
+struct ScoredHand {
+    cards: String,
+    hand_type: HandType,
+    // what the wild cards were played as, None if the hand has no wild cards.
+    substitute: Option<char>,
+    // 1 is the weakest hand.
+    rank: usize,
+    bid: Bid,
+    winnings: usize,
+}
+
+// score all hands in the file, returned from weakest to strongest.
+fn score_hands(filename: &str, rules: &RankingRules) -> Vec<ScoredHand> { 
     let lines = get_lines(filename);
 
     // store the hand, the score and the bid.
-    let mut hands: Vec<(Hand, HandScore, Bid)> = Vec::new();
+    let mut hands: Vec<(&str, Hand, HandScore, Bid)> = Vec::new();
     for line in lines.iter() {
         let mut line_iter = line.split_whitespace();
-        let hand = parse_hand(line_iter.next().unwrap(), with_joker);
+        let cards = line_iter.next().unwrap();
+        let hand = parse_hand(cards, rules);
         let bid = line_iter.next().unwrap().parse::<usize>().unwrap();
-        let score = calc_hand_score(hand);
-        hands.push((hand, score, bid));
+        let score = calc_hand_score(&hand, rules);
+        hands.push((cards, hand, score, bid));
     }
 
-    hands.sort_by(|a, b| a.1.cmp(&b.1));
-    let mut sum = 0;
-    for (i, hand) in hands.iter().enumerate() {
-        sum += (i + 1) * hand.2;
+    hands.sort_by_key(|h| h.2);
+    hands.iter().enumerate().map(|(i, (cards, hand, _, bid))| ScoredHand {
+        cards: cards.to_string(),
+        hand_type: calc_hand_type(hand, rules),
+        substitute: wild_substitute(hand, rules),
+        rank: i + 1,
+        bid: *bid,
+        winnings: (i + 1) * bid,
+    }).collect()
+}
+
+fn hands_table(hands: &[ScoredHand]) -> String {
+    let mut table = format!("{:>6} {:<8} {:<14} {:>5} {:>8} {:>10}\n", "rank", "hand", "type", "wild", "bid", "winnings");
+    for h in hands.iter() {
+        let wild = h.substitute.map_or("-".to_string(), |c| c.to_string());
+        table += &format!("{:>6} {:<8} {:<14} {:>5} {:>8} {:>10}\n",
+                          h.rank, h.cards, format!("{:?}", h.hand_type), wild, h.bid, h.winnings);
     }
-    return sum;
+    table
+}
+
+fn solve(filename: &str, rules: &RankingRules) -> usize { 
+    score_hands(filename, rules).iter().map(|h| h.winnings).sum()
 }
 
 fn part1(filename: &str) -> usize {
-    solve(filename, false)
+    solve(filename, &RankingRules::standard())
 }
 
 fn part2(filename: &str) -> usize {
-    solve(filename, true)
+    solve(filename, &RankingRules::jokers())
 }
 
 mod tests {
@@ -130,23 +215,55 @@ mod tests {
 
     #[test]
     fn test_hand_score() {
-        assert_eq!(calc_hand_score(parse_hand("AAAAA", false)), 0x7EEEEE);
-        assert_eq!(calc_hand_score(parse_hand("KTJJT", false)), 0x3DABBA);
-        assert_eq!(calc_hand_score(parse_hand("23456", false)), 0x123456);
-        assert_eq!(calc_hand_score(parse_hand("JKKK2", true)) < calc_hand_score(parse_hand("QQQQ2", true)), true);
+        let rules = RankingRules::standard();
+        let jokers = RankingRules::jokers();
+        assert_eq!(calc_hand_score(&parse_hand("AAAAA", &rules), &rules), 0x7EEEEE);
+        assert_eq!(calc_hand_score(&parse_hand("KTJJT", &rules), &rules), 0x3DABBA);
+        assert_eq!(calc_hand_score(&parse_hand("23456", &rules), &rules), 0x123456);
+        assert_eq!(calc_hand_score(&parse_hand("JKKK2", &jokers), &jokers) < calc_hand_score(&parse_hand("QQQQ2", &jokers), &jokers), true);
     }
 
     #[test]
     fn test_hand_type_with_joker() {
-        assert_eq!(calc_hand_type(parse_hand("AAAAA", true)), HandType::FiveOfAKind);
-        assert_eq!(calc_hand_type(parse_hand("AAAJJ", true)), HandType::FiveOfAKind);
-        assert_eq!(calc_hand_type(parse_hand("JJJJJ", true)), HandType::FiveOfAKind);
-        assert_eq!(calc_hand_type(parse_hand("JJJJT", true)), HandType::FiveOfAKind);
-        assert_eq!(calc_hand_type(parse_hand("QJJQ2", true)), HandType::FourOfAKind);
-        assert_eq!(calc_hand_type(parse_hand("23456", true)), HandType::HighCard);
-        assert_eq!(calc_hand_type(parse_hand("2345J", true)), HandType::OnePair);
-        assert_eq!(calc_hand_type(parse_hand("JKKK2", true)), HandType::FourOfAKind);
-        assert_eq!(calc_hand_type(parse_hand("QQQQ2", true)), HandType::FourOfAKind);
+        let rules = RankingRules::jokers();
+        assert_eq!(calc_hand_type(&parse_hand("AAAAA", &rules), &rules), HandType::FiveOfAKind);
+        assert_eq!(calc_hand_type(&parse_hand("AAAJJ", &rules), &rules), HandType::FiveOfAKind);
+        assert_eq!(calc_hand_type(&parse_hand("JJJJJ", &rules), &rules), HandType::FiveOfAKind);
+        assert_eq!(calc_hand_type(&parse_hand("JJJJT", &rules), &rules), HandType::FiveOfAKind);
+        assert_eq!(calc_hand_type(&parse_hand("QJJQ2", &rules), &rules), HandType::FourOfAKind);
+        assert_eq!(calc_hand_type(&parse_hand("23456", &rules), &rules), HandType::HighCard);
+        assert_eq!(calc_hand_type(&parse_hand("2345J", &rules), &rules), HandType::OnePair);
+        assert_eq!(calc_hand_type(&parse_hand("JKKK2", &rules), &rules), HandType::FourOfAKind);
+        assert_eq!(calc_hand_type(&parse_hand("QQQQ2", &rules), &rules), HandType::FourOfAKind);
+    }
+
+    #[test]
+    fn test_hand_type_variants() {
+        // both jokers and deuces are wild.
+        let rules = RankingRules::new("AKQT98765432J", "J2", 5);
+        assert_eq!(calc_hand_type(&parse_hand("2J345", &rules), &rules), HandType::ThreeOfAKind);
+        assert_eq!(calc_hand_type(&parse_hand("22JKK", &rules), &rules), HandType::FiveOfAKind);
+        assert_eq!(calc_hand_type(&parse_hand("2QQKK", &rules), &rules), HandType::FullHouse);
+
+        // six card hands.
+        let rules = RankingRules::new("AKQJT98765432", "", 6);
+        assert_eq!(calc_hand_type(&parse_hand("AAAKKK", &rules), &rules), HandType::FullHouse);
+        assert_eq!(calc_hand_type(&parse_hand("AAKKQQ", &rules), &rules), HandType::TwoPair);
+        assert_eq!(calc_hand_type(&parse_hand("AAAAAK", &rules), &rules), HandType::FiveOfAKind);
+        assert_eq!(calc_hand_type(&parse_hand("23456A", &rules), &rules), HandType::HighCard);
+    }
+
+    #[test]
+    fn test_scored_hands() {
+        let hands = score_hands("files/day07_sample.txt", &RankingRules::jokers());
+        println!("{}", hands_table(&hands));
+        let order = hands.iter().map(|h| h.cards.as_str()).collect::<Vec<&str>>();
+        assert_eq!(order, vec!["32T3K", "KK677", "T55J5", "QQQJA", "KTJJT"]);
+        assert_eq!(hands[4].hand_type, HandType::FourOfAKind);
+        assert_eq!(hands[4].substitute, Some('T'));
+        assert_eq!(hands[4].winnings, 5 * 220);
+        assert_eq!(hands[1].substitute, None);
+        assert_eq!(wild_substitute(&parse_hand("JJJJJ", &RankingRules::jokers()), &RankingRules::jokers()), Some('A'));
     }
 
     #[test]
@@ -156,4 +273,4 @@ mod tests {
         assert_eq!(part2("files/day07_sample.txt"), 5905);
         assert_eq!(part2("files/day07_input.txt"), 250577259);
     }
-}
\ No newline at end of file
+}
@@ -1,27 +1,59 @@
 use crate::common::get_lines;
 use std::collections::HashMap;
 use regex::Regex;
-use num::integer;
+use num::integer::{ExtendedGcd, Integer};
+use itertools::Itertools;
+
+// the nodes of the network, interned so that each name maps to a dense index and following an
+// edge is just array indexing.
+struct Network {
+    names: Vec<String>,
+    ids: HashMap<String, u32>,
+    // left and right neighbor of each node.
+    edges: Vec<[u32; 2]>,
+}
 
-fn node_to_id(s: &str) -> usize {
-    let mut num: usize = 0;
-    for ch in s.chars() {
-        num = num << 8 | ch as usize;
+impl Network {
+    fn new() -> Network {
+        Network { names: Vec::new(), ids: HashMap::new(), edges: Vec::new() }
+    }
+
+    // get the id for a node, adding it to the network if it's new.
+    fn intern(&mut self, name: &str) -> u32 {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = self.names.len() as u32;
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        // point at itself until we know the real neighbors.
+        self.edges.push([id, id]);
+        id
+    }
+
+    fn id(&self, name: &str) -> Option<u32> {
+        self.ids.get(name).copied()
     }
-    return num;
-}
 
-fn id_to_str(id: usize) -> String {
-    let mut num = id;
-    let mut s = String::new();
-    while num > 0 {
-        s.push((num & 0xFF) as u8 as char);
-        num = num >> 8;
+    fn name(&self, id: u32) -> &str {
+        &self.names[id as usize]
+    }
+
+    fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    fn next(&self, node: u32, dir: usize) -> u32 {
+        self.edges[node as usize][dir]
+    }
+
+    // all nodes whose name ends with the given character.
+    fn nodes_ending_with(&self, ch: char) -> Vec<u32> {
+        (0..self.len() as u32).filter(|&id| self.name(id).ends_with(ch)).collect()
     }
-    return s.chars().rev().collect::<String>();
 }
 
-fn parse_input(filename: &str) -> (Vec<usize>, HashMap<usize, [usize; 2]>) {
+fn parse_input(filename: &str) -> (Vec<usize>, Network) {
     let lines = get_lines(filename);
     let mut line_iter = lines.iter();
 
@@ -40,38 +72,71 @@ fn parse_input(filename: &str) -> (Vec<usize>, HashMap<usize, [usize; 2]>) {
 
     // println!("driection = {}", direction);
 
-    let re = Regex::new(r"([A-Z]+).*([A-Z][A-Z][A-Z]).*([A-Z][A-Z][A-Z]).*$").unwrap();
+    let re = Regex::new(r"^\s*([[:alnum:]]+)\s*=\s*\(\s*([[:alnum:]]+)\s*,\s*([[:alnum:]]+)\s*\)\s*$").unwrap();
 
-    let mut map: HashMap<usize, [usize; 2]> = HashMap::new();
+    let mut network = Network::new();
+    let mut defined: Vec<bool> = Vec::new();
     for line in line_iter { 
         // println!("line = {}", line);
         let Some(caps) = re.captures(line) else { println!("{}, no match", line); continue; };
         // println!("caps = {:?}", caps);
-        let from = node_to_id(&caps[1]);
-        let to = [ node_to_id(&caps[2]), node_to_id(&caps[3]) ];
-        map.insert(from, to);
+        let from = network.intern(&caps[1]);
+        let to = [ network.intern(&caps[2]), network.intern(&caps[3]) ];
+        network.edges[from as usize] = to;
+        defined.resize(network.len(), false);
+        defined[from as usize] = true;
+    }
+
+    // a node we only know by name would otherwise quietly loop back to itself.
+    defined.resize(network.len(), false);
+    if let Some(id) = defined.iter().position(|d| !d) {
+        panic!("node {} is never defined", network.name(id as u32));
     }
-    // println!("map = {:?}", map);
-    return (dirs, map);
+    return (dirs, network);
+}
+
+// the network as a Graphviz DOT graph, starting nodes are green and ending nodes red. Render it with
+// e.g. `dot -Tsvg network.dot -o network.svg`.
+fn network_to_dot(network: &Network) -> String {
+    let mut dot = String::from("digraph network {\n");
+    for id in 0..network.len() as u32 {
+        let name = network.name(id);
+        let color = if name.ends_with('A') { "green" } else if name.ends_with('Z') { "red" } else { "black" };
+        dot += &format!("    \"{}\" [color={}];\n", name, color);
+    }
+    for id in 0..network.len() as u32 {
+        let [left, right] = network.edges[id as usize];
+        if left == right {
+            dot += &format!("    \"{}\" -> \"{}\" [label=\"LR\"];\n", network.name(id), network.name(left));
+        } else {
+            dot += &format!("    \"{}\" -> \"{}\" [label=\"L\"];\n", network.name(id), network.name(left));
+            dot += &format!("    \"{}\" -> \"{}\" [label=\"R\"];\n", network.name(id), network.name(right));
+        }
+    }
+    dot += "}\n";
+    dot
+}
+
+fn write_network_dot(filename: &str, dot_filename: &str) {
+    let (_, network) = parse_input(filename);
+    std::fs::write(dot_filename, network_to_dot(&network)).expect("Could not write file");
 }
 
 #[allow(dead_code)]
 fn part2_bruteforce(filename: &str) -> usize { 
-    let (dirs, map) = parse_input(filename);
+    let (dirs, network) = parse_input(filename);
 
     // find all nodes that end in A.
-    let mut curr_nodes = map.iter().filter(|(k,_)| -> bool {
-        (*k & 0xFF) == 'A' as usize
-    }).map(|(k,_)| -> usize { *k }).collect::<Vec<usize>>();
+    let mut curr_nodes = network.nodes_ending_with('A');
+    let is_end = network.nodes_ending_with('Z');
 
     let mut curr_idx = 0;
     let n = dirs.len();
 
     let mut n_steps = 0;
-    while !curr_nodes.iter().all(|node| -> bool { (node & 0xFF) == 'Z' as usize }) { 
-        for i in 0..curr_nodes.len() {
-            let curr = map.get(&curr_nodes[i]).unwrap()[dirs[curr_idx]];
-            curr_nodes[i] = curr;
+    while !curr_nodes.iter().all(|node| -> bool { is_end.contains(node) }) { 
+        for node in curr_nodes.iter_mut() {
+            *node = network.next(*node, dirs[curr_idx]);
         }
         n_steps += 1;
         curr_idx = (curr_idx + 1) % n;
@@ -81,8 +146,8 @@ fn part2_bruteforce(filename: &str) -> usize {
 }
 
 // advance node to the next end node. Return the new ending node + steps it took to get there. 
-fn steps_to_end((node, node_loc): (usize, usize), 
-                dirs: &Vec<usize>, map: &HashMap<usize, [usize; 2]>) -> (usize, usize) {
+fn steps_to_end((node, node_loc): (u32, usize), 
+                dirs: &[usize], network: &Network) -> (u32, usize) {
     let mut curr = node;
     let n = dirs.len();
 
@@ -91,13 +156,13 @@ fn steps_to_end((node, node_loc): (usize, usize),
     let mut n_steps = 0;
 
     // advance
-    curr = map.get(&curr).unwrap()[dirs[dir_loc]];
+    curr = network.next(curr, dirs[dir_loc]);
     n_steps += 1;
     dir_loc = (dir_loc + 1) % n;
 
     // iterate until we have a node that ends in Z.
-    while (curr & 0xFF) != 'Z' as usize { 
-        curr = map.get(&curr).unwrap()[dirs[dir_loc]];
+    while !network.name(curr).ends_with('Z') { 
+        curr = network.next(curr, dirs[dir_loc]);
         n_steps += 1;
         dir_loc = (dir_loc + 1) % n;
     }
@@ -105,57 +170,115 @@ fn steps_to_end((node, node_loc): (usize, usize),
     return (curr, n_steps);
 }
 
-fn part2_withlcm(filename: &str) -> usize {
-    let (dirs, map) = parse_input(filename);
-
-    // the starting nodes.
-    let start_nodes = map.iter().filter(|(k,_)| -> bool {
-        (*k & 0xFF) == 'A' as usize
-    }).map(|(k,_)| -> usize { *k }).collect::<Vec<usize>>();
+// how a single ghost moves through the network. The ghost's state is its node plus where it is in
+// the instructions, so after at most nodes * instructions steps it must repeat a state and loop
+// forever from there on.
+#[derive(Debug, PartialEq)]
+struct GhostCycle {
+    // number of steps before the ghost enters its loop.
+    pre_period: usize,
+    // length of the loop.
+    period: usize,
+    // steps at which the ghost is on a Z node before entering the loop.
+    pre_period_hits: Vec<usize>,
+    // steps at which the ghost is on a Z node during the first pass through the loop, these
+    // repeat every period steps.
+    cycle_hits: Vec<usize>,
+}
 
-    // store nodes as current location and steps taken to get there. 
-    let mut curr_nodes: Vec<(usize, usize)> = Vec::new();
-    for node in start_nodes.iter() {
-        curr_nodes.push((*node, 0));
+impl GhostCycle {
+    fn is_hit(&self, step: usize) -> bool {
+        if step < self.pre_period {
+            self.pre_period_hits.contains(&step)
+        } else {
+            let in_cycle = self.pre_period + (step - self.pre_period) % self.period;
+            self.cycle_hits.contains(&in_cycle)
+        }
     }
+}
 
-    // advance each node to the end. 
-    for i in 0..curr_nodes.len() {
-        let (node, steps) = curr_nodes[i];
-        curr_nodes[i] = steps_to_end((node, steps), &dirs, &map);
-    }
+fn analyse_cycle(start: u32, dirs: &[usize], network: &Network) -> GhostCycle {
+    let n = dirs.len();
+    // step at which each (node, instruction index) state was first seen.
+    let mut seen: Vec<Option<usize>> = vec![None; network.len() * n];
+    let mut hits: Vec<usize> = Vec::new();
 
-    // verify that each node cycles back to itself.
-    for i in 0..curr_nodes.len() {
-        let (node, location) = curr_nodes[i];
-        let (new_node, advanced_by) = steps_to_end((node, location), &dirs, &map);
-        if new_node != node {
-            panic!("node does not repeat itself!")
+    let mut curr = start;
+    let mut step = 0;
+    loop {
+        let state = curr as usize * n + step % n;
+        if let Some(first) = seen[state] {
+            let (pre_period_hits, cycle_hits) = hits.iter().partition(|&&h| h < first);
+            return GhostCycle { pre_period: first, period: step - first, pre_period_hits, cycle_hits };
         }
-        if advanced_by != location {
-            panic!("node is not cyclic without an offset!")
+        seen[state] = Some(step);
+        if network.name(curr).ends_with('Z') {
+            hits.push(step);
         }
+        curr = network.next(curr, dirs[step % n]);
+        step += 1;
     }
+}
+
+// combine x = a1 (mod m1) and x = a2 (mod m2) into a single congruence, the moduli don't have to be
+// coprime. Returns None if there is no solution.
+fn crt_combine((a1, m1): (i128, i128), (a2, m2): (i128, i128)) -> Option<(i128, i128)> {
+    let ExtendedGcd { gcd, x, .. } = m1.extended_gcd(&m2);
+    if (a2 - a1) % gcd != 0 {
+        return None;
+    }
+    let lcm = m1 / gcd * m2;
+    // solve m1 * k = a2 - a1 (mod m2) for k.
+    let k = ((a2 - a1) / gcd % (m2 / gcd) * x).rem_euclid(m2 / gcd);
+    Some(((a1 + m1 * k).rem_euclid(lcm), lcm))
+}
 
-    // can we do a reduce or fold here? 
-    let mut lcm = curr_nodes[0].1;
-    for i in 1..curr_nodes.len() {
-        lcm = integer::lcm(lcm, curr_nodes[i].1);
+// the first step at which every ghost is on a Z node at the same time.
+fn first_common_hit(cycles: &[GhostCycle]) -> Option<usize> {
+    // a hit before some ghost enters its loop has to be one of that ghost's pre-period hits, and
+    // there are only finitely many of those to check.
+    let early = cycles.iter()
+        .flat_map(|c| c.pre_period_hits.iter())
+        .filter(|&&step| cycles.iter().all(|c| c.is_hit(step)))
+        .min()
+        .copied();
+    if early.is_some() {
+        return early;
     }
-    return lcm;
+
+    // otherwise every ghost is in its loop, so pick one hit per ghost and solve the congruences.
+    let min_step = cycles.iter().map(|c| c.pre_period).max()? as i128;
+    let mut best: Option<usize> = None;
+    for choice in cycles.iter().map(|c| c.cycle_hits.iter()).multi_cartesian_product() {
+        let solution = cycles.iter().zip(choice.iter())
+            .try_fold((0, 1), |acc, (c, &&hit)| crt_combine(acc, (hit as i128, c.period as i128)));
+        let Some((a, m)) = solution else { continue; };
+        // smallest solution that is at least min_step.
+        let step = if a >= min_step { a } else { a + (min_step - a + m - 1) / m * m };
+        best = Some(best.map_or(step as usize, |b| b.min(step as usize)));
+    }
+    best
+}
+
+fn part2_withcrt(filename: &str) -> usize {
+    let (dirs, network) = parse_input(filename);
+
+    let cycles = network.nodes_ending_with('A').iter()
+        .map(|&start| analyse_cycle(start, &dirs, &network))
+        .collect::<Vec<GhostCycle>>();
+
+    first_common_hit(&cycles).expect("ghosts never reach Z nodes at the same time")
 }
 
 fn part2(filename: &str) -> usize { 
-    let (dirs, map) = parse_input(filename);
+    let (dirs, network) = parse_input(filename);
     let n_dirs = dirs.len();
 
     // the starting nodes.
-    let start_nodes = map.iter().filter(|(k,_)| -> bool {
-        (*k & 0xFF) == 'A' as usize
-    }).map(|(k,_)| -> usize { *k }).collect::<Vec<usize>>();
+    let start_nodes = network.nodes_ending_with('A');
 
     // store nodes as current location and steps taken to get there. 
-    let mut curr_nodes: Vec<(usize, usize)> = Vec::new();
+    let mut curr_nodes: Vec<(u32, usize)> = Vec::new();
     for node in start_nodes.iter() {
         curr_nodes.push((*node, 0));
     }
@@ -164,12 +287,12 @@ fn part2(filename: &str) -> usize {
     // advance each node to the end. 
     for i in 0..curr_nodes.len() {
         let (node, steps) = curr_nodes[i];
-        curr_nodes[i] = steps_to_end((node, steps), &dirs, &map);
+        curr_nodes[i] = steps_to_end((node, steps), &dirs, &network);
     }
 
     // cache the node and steps to get to the next ending Z.
     // nodes + steps -> (node, steps % dirs.len())
-    let mut cache: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+    let mut cache: HashMap<(u32, usize), (u32, usize)> = HashMap::new();
 
     // until all nodes are at the end with the same number of steps, advance each node to next z.
     let mut curr_max_steps = curr_nodes.iter().map(|(_,steps)| -> usize { *steps }).max().unwrap();
@@ -182,7 +305,7 @@ fn part2(filename: &str) -> usize {
                     let (new_node, advanced_by) = cache.get(&(node, dir_loc)).unwrap();
                     curr_nodes[i] = (*new_node, steps + advanced_by);
                 } else {
-                    let (new_node, advanced_by) = steps_to_end((node, steps), &dirs, &map);
+                    let (new_node, advanced_by) = steps_to_end((node, steps), &dirs, &network);
                     curr_nodes[i] = (new_node, steps + advanced_by);
                     cache.insert((node, dir_loc), (new_node, advanced_by));
                 }
@@ -193,17 +316,17 @@ fn part2(filename: &str) -> usize {
 
     println!("final config:");
     for (node, steps) in curr_nodes.iter() {
-        println!("node = {}, steps = {}", id_to_str(*node), steps);
+        println!("node = {}, steps = {}", network.name(*node), steps);
     }
 
     return curr_max_steps;
 }
 
 fn part1(filename: &str) -> usize {
-    let (dirs, map) = parse_input(filename);
+    let (dirs, network) = parse_input(filename);
 
-    let start = node_to_id("AAA");
-    let end = node_to_id("ZZZ");
+    let start = network.id("AAA").expect("no AAA node");
+    let end = network.id("ZZZ").expect("no ZZZ node");
 
     let mut curr = start;
     let mut curr_idx = 0;
@@ -211,7 +334,7 @@ fn part1(filename: &str) -> usize {
 
     let mut n_steps = 0;
     while curr != end { 
-        curr = map.get(&curr).unwrap()[dirs[curr_idx]];
+        curr = network.next(curr, dirs[curr_idx]);
         n_steps += 1;
         curr_idx = (curr_idx + 1) % n;
     }
@@ -222,12 +345,64 @@ fn part1(filename: &str) -> usize {
 mod tests {
     use super::*;
 
+    #[test]
+    #[should_panic(expected = "node CCC is never defined")]
+    fn test_undefined_node() {
+        parse_input("files/day08_undefined.txt");
+    }
+
+    #[test]
+    fn test_network() {
+        let (dirs, network) = parse_input("files/day08_sample4.txt");
+        assert_eq!(dirs, vec![0, 1]);
+        assert_eq!(network.len(), 8);
+        let start = network.id("GHOST22A").unwrap();
+        assert_eq!(network.name(network.next(start, 0)), "GHOST22B");
+        assert_eq!(network.nodes_ending_with('A').len(), 2);
+        assert_eq!(part2("files/day08_sample4.txt"), 6);
+    }
+
+    #[test]
+    fn test_dot() {
+        let (_, network) = parse_input("files/day08_sample.txt");
+        let dot = network_to_dot(&network);
+        assert!(dot.starts_with("digraph network {"));
+        assert!(dot.contains("\"AAA\" [color=green];"));
+        assert!(dot.contains("\"ZZZ\" [color=red];"));
+        assert!(dot.contains("\"AAA\" -> \"BBB\" [label=\"L\"];"));
+        assert!(dot.contains("\"AAA\" -> \"CCC\" [label=\"R\"];"));
+        assert!(dot.contains("\"ZZZ\" -> \"ZZZ\" [label=\"LR\"];"));
+
+        let out = std::env::temp_dir().join("day08_sample.dot");
+        write_network_dot("files/day08_sample.txt", out.to_str().unwrap());
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), dot);
+    }
+
+    #[test]
+    fn test_cycles() {
+        // the ghosts first reach Z after a different number of steps than their loop length.
+        let (dirs, network) = parse_input("files/day08_sample5.txt");
+        let cycle = analyse_cycle(network.id("BBA").unwrap(), &dirs, &network);
+        assert_eq!(cycle, GhostCycle { pre_period: 1, period: 3, pre_period_hits: vec![], cycle_hits: vec![1] });
+        assert_eq!(part2_withcrt("files/day08_sample5.txt"), 4);
+
+        // one ghost only passes a Z node once on its way into the loop.
+        let once = GhostCycle { pre_period: 2, period: 1, pre_period_hits: vec![1], cycle_hits: vec![] };
+        assert_eq!(first_common_hit(&[cycle, once]), Some(1));
+
+        // incompatible loops never line up.
+        let even = GhostCycle { pre_period: 0, period: 2, pre_period_hits: vec![], cycle_hits: vec![0] };
+        let odd = GhostCycle { pre_period: 0, period: 4, pre_period_hits: vec![], cycle_hits: vec![1, 3] };
+        assert_eq!(first_common_hit(&[even, odd]), None);
+    }
+
     #[test]
     fn test() {
         assert_eq!(part1("files/day08_sample.txt"), 2);
         assert_eq!(part1("files/day08_sample2.txt"), 6);
         assert_eq!(part1("files/day08_input.txt"), 13771);
         assert_eq!(part2("files/day08_sample3.txt"), 6);
-        assert_eq!(part2_withlcm("files/day08_input.txt"), 13129439557681);
+        assert_eq!(part2_withcrt("files/day08_sample3.txt"), 6);
+        assert_eq!(part2_withcrt("files/day08_input.txt"), 13129439557681);
     }
 }
\ No newline at end of file
@@ -1,57 +1,176 @@
 use crate::common::get_lines;
+use num::{BigInt, BigRational, One, ToPrimitive, Zero};
 
-fn next_num(nums: &Vec<isize>) -> isize {
-    if nums.iter().all(|n| *n == 0) {
-        0
-    } else { 
-        let diffs = nums.windows(2).map(|w| w[1] - w[0]).collect::<Vec<isize>>();
-        let next = next_num(&diffs) + nums.last().unwrap();
-        next
-    }
+#[derive(Debug, PartialEq)]
+enum SequenceError {
+    // a number in the input couldn't be parsed.
+    InvalidNumber(String),
+    // the differences never became all zero before running out of numbers, so there isn't enough
+    // data to tell which polynomial generates the sequence (e.g. for exponential sequences).
+    InsufficientData,
+    // a result doesn't fit in an isize.
+    Overflow,
+}
+
+// a sequence generated by a polynomial in its index, with index 0 being the first number.
+// The difference table is built once and only its leading diagonal is kept: by Newton's forward
+// difference formula the value at index n is the sum of leading[k] * C(n, k).
+struct Sequence {
+    leading: Vec<BigInt>,
+    len: usize,
 }
 
-fn prev_num(nums: &Vec<isize>) -> isize {
-    if nums.iter().all(|n| *n == 0) {
-        0
-    } else { 
-        let diffs = nums.windows(2).map(|w| w[1] - w[0]).collect::<Vec<isize>>();
-        let prev = nums.first().unwrap() - prev_num(&diffs);
-        prev
+impl Sequence {
+    fn new(nums: &[isize]) -> Result<Sequence, SequenceError> {
+        let mut leading = Vec::new();
+        let mut row = nums.iter().map(|&n| BigInt::from(n)).collect::<Vec<BigInt>>();
+        loop {
+            if row.is_empty() {
+                return Err(SequenceError::InsufficientData);
+            }
+            if row.iter().all(|n| n.is_zero()) {
+                return Ok(Sequence { leading, len: nums.len() });
+            }
+            leading.push(row[0].clone());
+            for i in 0..row.len() - 1 {
+                row[i] = &row[i + 1] - &row[i];
+            }
+            row.pop();
+        }
+    }
+
+    fn parse(line: &str) -> Result<Sequence, SequenceError> {
+        let nums = line.split_whitespace()
+            .map(|s| s.parse::<isize>().map_err(|_| SequenceError::InvalidNumber(s.to_string())))
+            .collect::<Result<Vec<isize>, SequenceError>>()?;
+        Sequence::new(&nums)
+    }
+
+    // degree of the generating polynomial, the all-zero sequence has degree 0.
+    fn degree(&self) -> usize {
+        self.leading.len().saturating_sub(1)
+    }
+
+    // value at any index, negative indices are before the start of the sequence.
+    fn value_at(&self, n: isize) -> BigInt {
+        let n = BigInt::from(n);
+        let mut value = BigInt::zero();
+        // C(n, k), which also works for negative n.
+        let mut binomial = BigInt::one();
+        for (k, d) in self.leading.iter().enumerate() {
+            value += d * &binomial;
+            binomial = binomial * (&n - k) / (k + 1);
+        }
+        value
+    }
+
+    // the next k values after the end of the sequence.
+    fn next_values(&self, k: usize) -> Vec<BigInt> {
+        (0..k).map(|i| self.value_at((self.len + i) as isize)).collect()
+    }
+
+    // the k values before the start of the sequence, closest first.
+    fn prev_values(&self, k: usize) -> Vec<BigInt> {
+        (1..=k).map(|i| self.value_at(-(i as isize))).collect()
+    }
+
+    // the number right after the end of the sequence.
+    fn next(&self) -> Result<isize, SequenceError> {
+        self.value_at(self.len as isize).to_isize().ok_or(SequenceError::Overflow)
+    }
+
+    // the number right before the start of the sequence.
+    fn prev(&self) -> Result<isize, SequenceError> {
+        self.value_at(-1).to_isize().ok_or(SequenceError::Overflow)
+    }
+
+    // coefficients of the generating polynomial in the index, constant term first.
+    fn coefficients(&self) -> Vec<BigRational> {
+        let mut coefficients = vec![BigRational::zero(); self.degree() + 1];
+        // n * (n - 1) * ... * (n - k + 1) / k! in the power basis.
+        let mut binomial = vec![BigRational::one()];
+        for (k, d) in self.leading.iter().enumerate() {
+            for (c, b) in coefficients.iter_mut().zip(binomial.iter()) {
+                *c += b * BigRational::from(d.clone());
+            }
+            // multiply by (n - k) / (k + 1).
+            let scale = BigRational::from(BigInt::from(k + 1));
+            let mut next = vec![BigRational::zero(); binomial.len() + 1];
+            for (i, b) in binomial.iter().enumerate() {
+                next[i + 1] += b / &scale;
+                next[i] -= b * BigRational::from(BigInt::from(k)) / &scale;
+            }
+            binomial = next;
+        }
+        coefficients
     }
 }
 
-fn part1(filename: &str) -> isize {
+fn part1(filename: &str) -> Result<isize, SequenceError> {
     let lines = get_lines(filename);
 
-    let mut sum = 0;
+    let mut sum: isize = 0;
     for line in lines.iter()  {
-        let nums = line.split_whitespace().map(|s| s.parse::<isize>().unwrap()).collect::<Vec<isize>>();
-        let next = next_num(&nums);
-        sum += next;
+        let next = Sequence::parse(line)?.next()?;
+        sum = sum.checked_add(next).ok_or(SequenceError::Overflow)?;
     }
-    return sum;
+    Ok(sum)
 }
 
-fn part2(filename: &str) -> isize {
+fn part2(filename: &str) -> Result<isize, SequenceError> {
     let lines = get_lines(filename);
 
-    let mut sum = 0;
+    let mut sum: isize = 0;
     for line in lines.iter()  {
-        let nums = line.split_whitespace().map(|s| s.parse::<isize>().unwrap()).collect::<Vec<isize>>();
-        let prev = prev_num(&nums);
-        sum += prev;
+        let prev = Sequence::parse(line)?.prev()?;
+        sum = sum.checked_add(prev).ok_or(SequenceError::Overflow)?;
     }
-    return sum;
+    Ok(sum)
 }
 
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sequence() {
+        let seq = Sequence::parse("1 3 6 10 15 21").unwrap();
+        assert_eq!(seq.degree(), 2);
+        assert_eq!(seq.next_values(3), vec![28.into(), 36.into(), 45.into()]);
+        assert_eq!(seq.prev_values(2), vec![0.into(), 0.into()]);
+        assert_eq!(seq.value_at(1_000_000_000), BigInt::from(500000001500000001_i64));
+        let half = BigRational::new(1.into(), 2.into());
+        assert_eq!(seq.coefficients(), vec![BigRational::one(), &half * BigInt::from(3), half]);
+
+        let seq = Sequence::parse("0 3 6 9 12 15").unwrap();
+        assert_eq!(seq.degree(), 1);
+        assert_eq!(seq.coefficients(), vec![BigRational::zero(), BigRational::from(BigInt::from(3))]);
+
+        let seq = Sequence::parse("0 0 0").unwrap();
+        assert_eq!(seq.degree(), 0);
+        assert_eq!(seq.next_values(1), vec![BigInt::zero()]);
+    }
+
+    #[test]
+    fn test_invalid_sequences() {
+        // powers of two never have all-zero differences.
+        assert_eq!(Sequence::parse("1 2 4 8 16 32").err(), Some(SequenceError::InsufficientData));
+        assert_eq!(Sequence::parse("").err(), Some(SequenceError::InsufficientData));
+        assert_eq!(Sequence::parse("1 x 3").err(), Some(SequenceError::InvalidNumber("x".to_string())));
+        // a constant needs to be seen twice before we know it's constant.
+        assert_eq!(Sequence::parse("7").err(), Some(SequenceError::InsufficientData));
+        assert_eq!(Sequence::parse("7 7").unwrap().next(), Ok(7));
+
+        let max = isize::MAX;
+        let seq = Sequence::new(&[max - 2, max - 1, max]).unwrap();
+        assert_eq!(seq.next(), Err(SequenceError::Overflow));
+        assert_eq!(seq.prev(), Ok(max - 3));
+    }
+
     #[test]
     fn test() {
-        assert_eq!(part1("files/day09_sample.txt"), 114);
-        assert_eq!(part1("files/day09_input.txt"), 1980437560);
-        assert_eq!(part2("files/day09_sample.txt"), 2);
-        assert_eq!(part2("files/day09_input.txt"), 977);
+        assert_eq!(part1("files/day09_sample.txt"), Ok(114));
+        assert_eq!(part1("files/day09_input.txt"), Ok(1980437560));
+        assert_eq!(part2("files/day09_sample.txt"), Ok(2));
+        assert_eq!(part2("files/day09_input.txt"), Ok(977));
     }
-}
\ No newline at end of file
+}
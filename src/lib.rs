@@ -1,5 +1,7 @@
 
 mod common;
+mod nonogram;
+mod polygon;
 mod day1;
 mod day2;
 mod day3;
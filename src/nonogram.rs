@@ -0,0 +1,170 @@
+use crate::day12::count_arrangements;
+use num::Zero;
+use std::collections::VecDeque;
+
+// A nonogram is a grid where the clue for each row and column lists the sizes of the runs of
+// filled cells in that line, which is the same constraint as a line of springs in day 12. Cells are
+// '#' (filled), '.' (empty) or '?' (not known yet).
+pub type Grid = Vec<Vec<char>>;
+
+#[derive(Debug, PartialEq)]
+pub enum Solution {
+    Unique(Grid),
+    // the puzzle has more than one solution, here are two of them.
+    Multiple(Grid, Grid),
+    None,
+}
+
+// parse clues like "3 1,1 5", one line per space separated clue. "0" or "" is an empty line.
+pub fn parse_clues(s: &str) -> Vec<Vec<usize>> {
+    s.split_whitespace().map(|clue| {
+        clue.split(',').filter_map(|n| n.parse::<usize>().ok()).filter(|&n| n > 0).collect()
+    }).collect()
+}
+
+pub fn grid_to_string(grid: &Grid) -> String {
+    grid.iter().map(|row| row.iter().collect::<String>() + "\n").collect()
+}
+
+// fill in every cell of the line that has the same value in all arrangements that agree with what we
+// know so far: a cell is filled if leaving it empty leaves no arrangements, and the other way
+// around. Returns false if the line can't be completed at all.
+fn solve_line(line: &mut [char], clue: &VecDeque<usize>) -> bool {
+    if count_arrangements(line, clue).is_zero() {
+        return false;
+    }
+    for i in 0..line.len() {
+        if line[i] != '?' {
+            continue;
+        }
+        line[i] = '#';
+        let filled = count_arrangements(line, clue);
+        line[i] = '.';
+        let empty = count_arrangements(line, clue);
+        line[i] = if filled.is_zero() {
+            '.'
+        } else if empty.is_zero() {
+            '#'
+        } else {
+            '?'
+        };
+    }
+    true
+}
+
+// repeatedly solve rows and columns until nothing changes. Returns false on a contradiction.
+fn propagate(grid: &mut Grid, rows: &[VecDeque<usize>], cols: &[VecDeque<usize>]) -> bool {
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (r, clue) in rows.iter().enumerate() {
+            let before = grid[r].clone();
+            if !solve_line(&mut grid[r], clue) {
+                return false;
+            }
+            changed |= grid[r] != before;
+        }
+        for (c, clue) in cols.iter().enumerate() {
+            let mut col = grid.iter().map(|row| row[c]).collect::<Vec<char>>();
+            let before = col.clone();
+            if !solve_line(&mut col, clue) {
+                return false;
+            }
+            if col != before {
+                changed = true;
+                for (row, ch) in grid.iter_mut().zip(col) {
+                    row[c] = ch;
+                }
+            }
+        }
+    }
+    true
+}
+
+// line solve as far as possible, then guess the first unknown cell and backtrack. Stops once two
+// solutions have been found.
+fn search(mut grid: Grid, rows: &[VecDeque<usize>], cols: &[VecDeque<usize>], solutions: &mut Vec<Grid>) {
+    if solutions.len() >= 2 || !propagate(&mut grid, rows, cols) {
+        return;
+    }
+    let unknown = grid.iter().enumerate()
+        .find_map(|(r, row)| row.iter().position(|&ch| ch == '?').map(|c| (r, c)));
+    match unknown {
+        None => solutions.push(grid),
+        Some((r, c)) => {
+            for guess in ['#', '.'] {
+                let mut next = grid.clone();
+                next[r][c] = guess;
+                search(next, rows, cols, solutions);
+            }
+        }
+    }
+}
+
+pub fn solve(rows: &[Vec<usize>], cols: &[Vec<usize>]) -> Solution {
+    let rows = rows.iter().map(|clue| clue.iter().copied().collect()).collect::<Vec<VecDeque<usize>>>();
+    let cols = cols.iter().map(|clue| clue.iter().copied().collect()).collect::<Vec<VecDeque<usize>>>();
+    let grid = vec![vec!['?'; cols.len()]; rows.len()];
+
+    let mut solutions = Vec::new();
+    search(grid, &rows, &cols, &mut solutions);
+    let mut solutions = solutions.into_iter();
+    match (solutions.next(), solutions.next()) {
+        (Some(a), Some(b)) => Solution::Multiple(a, b),
+        (Some(a), None) => Solution::Unique(a),
+        _ => Solution::None,
+    }
+}
+
+mod tests {
+    use super::*;
+
+    fn to_grid(s: &str) -> Grid {
+        s.lines().map(|line| line.chars().collect()).collect()
+    }
+
+    #[test]
+    fn test() {
+        let rows = parse_clues("3 1,1 5 1,1 1,1");
+        let cols = parse_clues("4 1,1 1,1 1,1 4");
+        let expected = to_grid(".###.\n#...#\n#####\n#...#\n#...#");
+        assert_eq!(solve(&rows, &cols), Solution::Unique(expected.clone()));
+        assert_eq!(grid_to_string(&expected), ".###.\n#...#\n#####\n#...#\n#...#\n");
+
+        // either diagonal works, so line solving alone gets nowhere.
+        let clues = parse_clues("1 1");
+        let Solution::Multiple(a, b) = solve(&clues, &clues) else { panic!("expected two solutions") };
+        assert_ne!(a, b);
+        assert!([to_grid("#.\n.#"), to_grid(".#\n#.")].contains(&a));
+
+        assert_eq!(solve(&parse_clues("2 0"), &parse_clues("0 1")), Solution::None);
+    }
+
+    #[test]
+    fn test_picture() {
+        // this 10x10 picture has other solutions too, the two we get back should both fit the clues.
+        let picture = to_grid("\
+..######..
+.#......#.
+#..#..#..#
+#........#
+#.#....#.#
+#..####..#
+.#......#.
+..######..
+....##....
+...####...");
+        let clues = |grid: &Grid| {
+            let line_clues = |lines: Vec<Vec<char>>| lines.iter().map(|line| {
+                line.iter().collect::<String>().split('.').filter(|g| !g.is_empty()).map(|g| g.len()).collect()
+            }).collect::<Vec<Vec<usize>>>();
+            let cols = (0..grid[0].len()).map(|c| grid.iter().map(|row| row[c]).collect()).collect();
+            (line_clues(grid.clone()), line_clues(cols))
+        };
+        let (rows, cols) = clues(&picture);
+        let Solution::Multiple(a, b) = solve(&rows, &cols) else { panic!("expected two solutions") };
+        assert_ne!(a, b);
+        assert_eq!(clues(&a), (rows.clone(), cols.clone()));
+        assert_eq!(clues(&b), (rows, cols));
+    }
+}
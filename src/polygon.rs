@@ -0,0 +1,30 @@
+pub type Position = (isize, isize);
+
+// twice the signed area of a simple polygon, using the shoelace formula.
+// https://en.wikipedia.org/wiki/Shoelace_formula
+pub fn shoelace_area2(pts: &[Position]) -> isize {
+    let mut area = 0;
+    for i in 0..pts.len() {
+        let j = (i + 1) % pts.len();
+        area += (pts[i].1 + pts[j].1) * (pts[i].0 - pts[j].0);
+    }
+    area
+}
+
+// number of lattice points strictly inside a polygon with lattice vertices, where boundary is the
+// number of lattice points on its edges.
+// Pick's theorem states that A = i + b/2 - 1, where b is the boundary.
+// https://en.wikipedia.org/wiki/Pick%27s_theorem
+pub fn interior_points(pts: &[Position], boundary: isize) -> isize {
+    (shoelace_area2(pts).abs() - boundary) / 2 + 1
+}
+
+// area of a polygon drawn on a grid, counting the grid cells on its boundary as well as the ones
+// inside. For axis aligned edges the boundary is the perimeter.
+pub fn polygon_area(pts: &[Position], perimeter: isize) -> isize {
+    // We want to include the boundary in our area: 
+    // A + b = (i + b/2 - 1) + b/2 + 1
+    //              ^           ^   
+    //         from shoelace   add boundary
+    interior_points(pts, perimeter) + perimeter
+}